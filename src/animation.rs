@@ -0,0 +1,295 @@
+use raylib::prelude::*;
+use std::io;
+use crate::camera::Camera;
+use crate::scene_file::SceneFile;
+
+/// curva de velocidad dentro de un tramo (de una clave a la siguiente)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "in",
+            Easing::EaseOut => "out",
+            Easing::EaseInOut => "inout",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "linear" => Some(Easing::Linear),
+            "in" => Some(Easing::EaseIn),
+            "out" => Some(Easing::EaseOut),
+            "inout" => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+}
+
+/// cómo se unen las claves
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interp {
+    /// spline que pasa por todas las claves (tangentes con los vecinos)
+    CatmullRom,
+    /// tramos cúbicos de Bézier entre claves, con asas según el tiempo de los vecinos: pasa por cada
+    /// clave a su hora y la velocidad no salta aunque las claves no estén equiespaciadas
+    Bezier,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32,      // segundos
+    pub eye: Vector3,
+    pub center: Vector3,
+    pub up: Vector3,    // referencia de arriba (roll)
+    pub fov_y: f32,     // rad
+    pub focus_dist: f32,
+    pub ease: Easing,   // del tramo que empieza en esta clave
+}
+
+/// recorrido de cámara por claves
+pub struct CameraPath {
+    pub keys: Vec<Keyframe>,
+    pub interp: Interp,
+}
+
+impl CameraPath {
+    /// formato (ver `assets/flythrough.txt`):
+    /// `interp catmull-rom|bezier` y una línea por clave:
+    /// `key t  ex ey ez  cx cy cz  fov(grados) foco [linear|in|out|inout] [up x y z]`
+    pub fn from_scene_file(sf: &SceneFile) -> Self {
+        let interp = match sf.get_str("interp") {
+            Some("bezier") => Interp::Bezier,
+            _ => Interp::CatmullRom,
+        };
+        let mut keys: Vec<Keyframe> = sf.get_all("key").filter_map(|v| {
+            let f = |i: usize| v.get(i).and_then(|s| s.parse::<f32>().ok());
+            let mut key = Keyframe {
+                time: f(0)?,
+                eye: Vector3::new(f(1)?, f(2)?, f(3)?),
+                center: Vector3::new(f(4)?, f(5)?, f(6)?),
+                up: Vector3::new(0.0, 1.0, 0.0),
+                fov_y: f(7)?.to_radians(),
+                focus_dist: f(8)?,
+                ease: Easing::Linear,
+            };
+            // opcionales, en cualquier orden
+            let mut i = 9;
+            while let Some(tok) = v.get(i) {
+                if tok == "up" {
+                    key.up = Vector3::new(f(i + 1)?, f(i + 2)?, f(i + 3)?);
+                    i += 4;
+                } else {
+                    key.ease = Easing::parse(tok)?;
+                    i += 1;
+                }
+            }
+            Some(key)
+        }).collect();
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keys, interp }
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let path = Self::from_scene_file(&SceneFile::load(path)?);
+        if path.keys.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "el recorrido no tiene claves"));
+        }
+        Ok(path)
+    }
+
+    pub fn to_scene_file(&self) -> SceneFile {
+        let mut sf = SceneFile::default();
+        let interp = match self.interp { Interp::CatmullRom => "catmull-rom", Interp::Bezier => "bezier" };
+        sf.set("interp", &[interp.to_string()]);
+        for k in &self.keys {
+            let mut vals: Vec<String> = [
+                k.time, k.eye.x, k.eye.y, k.eye.z, k.center.x, k.center.y, k.center.z,
+                k.fov_y.to_degrees(), k.focus_dist,
+            ].iter().map(f32::to_string).collect();
+            vals.push(k.ease.name().to_string());
+            vals.extend(["up".to_string(), k.up.x.to_string(), k.up.y.to_string(), k.up.z.to_string()]);
+            sf.push("key", &vals);
+        }
+        sf
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        self.to_scene_file().save(path)
+    }
+
+    pub fn duration(&self) -> f32 {
+        match (self.keys.first(), self.keys.last()) {
+            (Some(a), Some(b)) => b.time - a.time,
+            _ => 0.0,
+        }
+    }
+
+    /// parámetro continuo s ∈ [0, n-1] (índice de clave + avance suavizado en el tramo)
+    fn param(&self, t: f32) -> f32 {
+        let k = &self.keys;
+        if t <= k[0].time { return 0.0; }
+        for i in 0..k.len() - 1 {
+            if t < k[i + 1].time {
+                let span = (k[i + 1].time - k[i].time).max(1e-6);
+                return i as f32 + k[i].ease.apply((t - k[i].time) / span);
+            }
+        }
+        (k.len() - 1) as f32
+    }
+
+    /// evalúa un canal (posición, centro, fov o foco) en el parámetro s
+    fn eval<T>(&self, s: f32, get: impl Fn(&Keyframe) -> T) -> T
+    where T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<f32, Output = T> {
+        let k = &self.keys;
+        let n = k.len();
+        if n == 1 { return get(&k[0]); }
+        match self.interp {
+            Interp::CatmullRom => {
+                let i = (s.floor() as usize).min(n - 2);
+                let t = s - i as f32;
+                // en los extremos se repite la clave
+                let p0 = get(&k[i.saturating_sub(1)]);
+                let (p1, p2) = (get(&k[i]), get(&k[i + 1]));
+                let p3 = get(&k[(i + 2).min(n - 1)]);
+                let (t2, t3) = (t * t, t * t * t);
+                // forma de Hermite con tangentes (p2 - p0)/2 y (p3 - p1)/2
+                p1 * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + (p2 - p0) * (0.5 * (t3 - 2.0 * t2 + t))
+                    + p2 * (-2.0 * t3 + 3.0 * t2)
+                    + (p3 - p1) * (0.5 * (t3 - t2))
+            }
+            Interp::Bezier => {
+                let i = (s.floor() as usize).min(n - 2);
+                let t = s - i as f32;
+                // tangente en la clave j por unidad de tiempo (de un solo lado en los extremos)
+                let tangent = |j: usize| {
+                    let (a, b) = (j.saturating_sub(1), (j + 1).min(n - 1));
+                    (get(&k[b]) - get(&k[a])) * (1.0 / (k[b].time - k[a].time).max(1e-6))
+                };
+                // asas a un tercio del tramo en tiempo
+                let third = (k[i + 1].time - k[i].time) / 3.0;
+                let (b0, b3) = (get(&k[i]), get(&k[i + 1]));
+                let b1 = b0 + tangent(i) * third;
+                let b2 = b3 - tangent(i + 1) * third;
+                let u = 1.0 - t;
+                b0 * (u * u * u) + b1 * (3.0 * u * u * t) + b2 * (3.0 * u * t * t) + b3 * (t * t * t)
+            }
+        }
+    }
+
+    /// coloca la cámara en el instante t (segundos desde el inicio del recorrido)
+    pub fn apply(&self, t: f32, cam: &mut Camera) {
+        let s = self.param(self.keys[0].time + t);
+        cam.eye = self.eval(s, |k| k.eye);
+        cam.center = self.eval(s, |k| k.center);
+        cam.fov_y = self.eval(s, |k| k.fov_y).clamp(0.05, 3.0);
+        cam.focus_at(self.eval(s, |k| k.focus_dist));
+        cam.up = self.eval(s, |k| k.up).normalized();
+        cam.update_basis_vectors();
+    }
+}
+
+/// graba el estado de la cámara cuadro a cuadro durante la sesión interactiva
+#[derive(Default)]
+pub struct Recorder {
+    keys: Vec<Keyframe>,
+    time: f32,
+    active: bool,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool { self.active }
+
+    pub fn start(&mut self) {
+        self.keys.clear();
+        self.time = 0.0;
+        self.active = true;
+    }
+
+    /// agrega la pose actual; `dt` = segundos desde el cuadro anterior
+    pub fn capture(&mut self, cam: &Camera, dt: f32) {
+        if !self.active { return; }
+        if !self.keys.is_empty() { self.time += dt; }
+        self.keys.push(Keyframe {
+            time: self.time,
+            eye: cam.eye,
+            center: cam.center,
+            up: cam.up,
+            fov_y: cam.fov_y,
+            focus_dist: cam.focus_dist,
+            ease: Easing::Linear,
+        });
+    }
+
+    /// termina la toma y la devuelve como recorrido (Catmull-Rom pasa por cada cuadro grabado)
+    pub fn stop(&mut self) -> CameraPath {
+        self.active = false;
+        CameraPath { keys: std::mem::take(&mut self.keys), interp: Interp::CatmullRom }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, x: f32, z: f32, fov: f32, ease: Easing) -> Keyframe {
+        Keyframe {
+            time, eye: Vector3::new(x, 1.0 + 0.1 * x, z), center: Vector3::new(0.0, 0.5, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0), fov_y: fov.to_radians(), focus_dist: 3.0 + x, ease,
+        }
+    }
+
+    #[test]
+    fn passes_through_every_key_at_its_time() {
+        // claves con espaciado desigual y easing distinto por tramo
+        let keys = vec![
+            key(1.0, 4.0, 0.0, 60.0, Easing::Linear),
+            key(1.5, 3.0, 3.0, 50.0, Easing::EaseIn),
+            key(4.0, -2.0, 4.0, 40.0, Easing::EaseInOut),
+            key(4.5, -4.0, 0.0, 70.0, Easing::EaseOut),
+            key(7.0, 0.0, -4.0, 60.0, Easing::Linear),
+        ];
+        for interp in [Interp::Bezier, Interp::CatmullRom] {
+            let path = CameraPath { keys: keys.clone(), interp };
+            let mut cam = Camera::new(Vector3::new(0.0, 1.0, 5.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+            for k in &keys {
+                path.apply(k.time - keys[0].time, &mut cam);
+                assert!((cam.eye - k.eye).length() < 1e-4, "{:?} en t={}: {:?} vs {:?}", interp, k.time, cam.eye, k.eye);
+                assert!((cam.fov_y - k.fov_y).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn bezier_velocity_is_continuous_at_keys() {
+        let keys = vec![
+            key(0.0, 0.0, 0.0, 60.0, Easing::Linear),
+            key(0.5, 1.0, 1.0, 60.0, Easing::Linear),
+            key(3.0, 3.0, -1.0, 60.0, Easing::Linear),
+        ];
+        let path = CameraPath { keys, interp: Interp::Bezier };
+        let mut cam = Camera::new(Vector3::new(0.0, 1.0, 5.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+        let mut at = |t: f32| { path.apply(t, &mut cam); cam.eye };
+        let h = 1e-3;
+        let before = (at(0.5) - at(0.5 - h)) / h;
+        let after = (at(0.5 + h) - at(0.5)) / h;
+        assert!((before - after).length() < 0.05 * before.length(), "{:?} vs {:?}", before, after);
+    }
+}
@@ -0,0 +1,43 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use crate::ray_intersect::ortho_basis;
+
+/// uso de la oclusión ambiental
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AoMode {
+    Off,
+    /// oscurece la luz del entorno en los rincones
+    Shading,
+    /// solo la oclusión, en gris (modo arcilla para revisar la geometría)
+    Only,
+}
+
+/// oclusión ambiental por muestreo del hemisferio en cada impacto primario
+#[derive(Clone, Copy, Debug)]
+pub struct AmbientOcclusion {
+    pub mode: AoMode,
+    pub radius: f32, // obstáculos más lejos no ocluyen
+    pub samples: u32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(radius: f32, samples: u32) -> Self {
+        Self { mode: AoMode::Off, radius, samples: samples.max(1) }
+    }
+
+    /// Off → Shading → Only → Off
+    pub fn next_mode(&mut self) {
+        self.mode = match self.mode {
+            AoMode::Off => AoMode::Shading,
+            AoMode::Shading => AoMode::Only,
+            AoMode::Only => AoMode::Off,
+        };
+    }
+}
+
+/// dirección en el hemisferio de n con densidad ∝ cosθ
+pub fn cosine_hemisphere(n: Vector3, u1: f32, u2: f32) -> Vector3 {
+    let (t, b) = ortho_basis(&n);
+    let (r, phi) = (u1.sqrt(), 2.0 * PI * u2);
+    (t * (r * phi.cos()) + b * (r * phi.sin()) + n * (1.0 - u1).max(0.0).sqrt()).normalized()
+}
@@ -1,11 +1,75 @@
 use raylib::prelude::*;
+use std::f32::consts::PI;
+use crate::ray_intersect::{ortho_basis, RayDiff};
+use crate::scene_file::SceneFile;
+use crate::motion::slerp_shortest;
 
+/// cómo se proyectan los pixeles en rayos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    /// rayos paralelos a forward; `height` = alto de la vista en unidades de mundo
+    Orthographic { height: f32 },
+    /// ojo de pez equidistante: ángulo ∝ radio, `fov` (rad) es el diámetro del círculo
+    Fisheye { fov: f32 },
+    /// panorama 360°×180° (la imagen debería ser 2:1)
+    Equirectangular,
+}
+
+/// salida estereoscópica
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stereo {
+    Off,
+    /// ojo izquierdo a la izquierda, derecho a la derecha (cada uno con la mitad del ancho)
+    SideBySide,
+    /// ojo izquierdo arriba, derecho abajo
+    TopBottom,
+    /// rojo-cian: rojo = luminancia del ojo izquierdo, verde y azul del derecho
+    Anaglyph,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// control interactivo: órbita alrededor de `center` o vuelo libre en primera persona
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vector3,
     pub center: Vector3,
     pub up: Vector3,
     pub forward: Vector3,
     pub right: Vector3,
+    /// orientación cámara→mundo (local: derecha = +x, arriba = +y, forward = -z);
+    /// forward/right/up se derivan de aquí
+    pub orientation: Quaternion,
+    pub fov_y: f32,          // campo de visión vertical (rad)
+    pub aspect: Option<f32>, // ancho/alto forzado; None = el de la imagen
+    pub near: f32,           // plano de recorte cercano (distancia sobre forward)
+    pub sensor_height: f32,  // alto del sensor en mm (35mm full frame = 24)
+    pub aperture: f32,       // radio de la lente en unidades de mundo (0 = estenopeica)
+    pub focus_dist: f32,     // distancia al plano de enfoque (sobre forward)
+    pub blades: u32,         // hojas del diafragma (bokeh poligonal); < 3 = circular
+    pub blade_rotation: f32, // giro del polígono (rad)
+    pub autofocus: bool,     // enfocar cada cuadro lo que haya al centro de la pantalla
+    pub projection: Projection,
+    pub mode: CameraMode,
+    pub shutter_open: f32,   // instante (s) en que abre el obturador
+    pub shutter: f32,        // tiempo de exposición (s); 0 = sin desenfoque de movimiento
+    /// pose (ojo, orientación) al cerrar el obturador; None = cámara quieta durante la exposición
+    pub motion_end: Option<(Vector3, Quaternion)>,
+    pub stereo: Stereo,
+    pub interocular: f32,    // separación entre ojos (unidades de mundo)
+    pub convergence: f32,    // distancia al plano de paralaje cero
+    pub lens_shift: f32,     // desplazamiento horizontal del frustum (en tan del ángulo); lo usa el estéreo
 }
 
 impl Camera {
@@ -14,30 +78,70 @@ impl Camera {
             eye, center, up,
             forward: Vector3::zero(),
             right: Vector3::zero(),
+            orientation: Quaternion::identity(),
+            fov_y: PI/3.0,
+            aspect: None,
+            near: 0.0,
+            sensor_height: 24.0,
+            aperture: 0.0,
+            focus_dist: (center - eye).length(),
+            blades: 0,
+            blade_rotation: 0.0,
+            autofocus: false,
+            projection: Projection::Perspective,
+            mode: CameraMode::Orbit,
+            shutter_open: 0.0,
+            shutter: 0.0,
+            motion_end: None,
+            stereo: Stereo::Off,
+            interocular: 0.065,
+            convergence: (center - eye).length(),
+            lens_shift: 0.0,
         };
         c.update_basis_vectors();
         c
     }
 
+    /// reconstruye la orientación mirando de `eye` a `center` con `up` como referencia
+    /// (si forward y up son paralelos conserva la derecha anterior)
     pub fn update_basis_vectors(&mut self) {
-        self.forward = (self.center - self.eye).normalized();
-        self.right = self.forward.cross(self.up).normalized();
-        self.up = self.right.cross(self.forward);
+        let f = (self.center - self.eye).normalized();
+        let mut r = f.cross(self.up);
+        if r.length() < 1e-5 {
+            r = self.right - f * self.right.dot(f);
+            if r.length() < 1e-5 { r = ortho_basis(&f).0; }
+        }
+        let r = r.normalized();
+        self.orientation = quat_from_basis(r, r.cross(f), -f);
+        self.apply_orientation();
     }
 
-    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
-        let rel = self.eye - self.center;
-        let r = rel.length();
-        let cur_yaw = rel.z.atan2(rel.x);
-        let cur_pitch = (rel.y / r).asin();
+    /// deriva la base de `orientation` y recoloca el centro a la misma distancia
+    fn apply_orientation(&mut self) {
+        let dist = (self.center - self.eye).length();
+        self.orientation = self.orientation.normalized();
+        self.forward = Vector3::new(0.0, 0.0, -1.0).rotate_by(self.orientation);
+        self.right = Vector3::new(1.0, 0.0, 0.0).rotate_by(self.orientation);
+        self.up = Vector3::new(0.0, 1.0, 0.0).rotate_by(self.orientation);
+        self.center = self.eye + self.forward * dist;
+    }
 
-        let ny = cur_yaw + yaw;
-        let np = (cur_pitch + pitch).clamp(-1.45, 1.45);
+    /// giro: yaw sobre +Y de mundo (mezclar yaw y pitch no acumula roll), pitch sobre la derecha
+    /// y roll sobre forward de la cámara
+    fn rotate(&mut self, yaw: f32, pitch: f32, roll: f32) {
+        let local = Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), pitch)
+            * Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, -1.0), roll);
+        self.orientation = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), yaw) * self.orientation * local;
+    }
 
-        let cp = np.cos();
-        let new_rel = Vector3::new(r * cp * ny.cos(), r * np.sin(), r * cp * ny.sin());
-        self.eye = self.center + new_rel;
-        self.update_basis_vectors();
+    /// órbita sobre la esfera alrededor de `center`: yaw alrededor de Y de mundo, pitch sin límite
+    /// (se puede pasar por encima del techo) y respetando el roll actual
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        let r = (self.eye - self.center).length();
+        self.rotate(-yaw, -pitch, 0.0);
+        let back = Vector3::new(0.0, 0.0, 1.0).rotate_by(self.orientation);
+        self.eye = self.center + back * r;
+        self.apply_orientation();
     }
 
     pub fn dolly(&mut self, amount: f32) {
@@ -46,7 +150,84 @@ impl Camera {
         // evita atravesar el centro
         if (new_eye - self.center).length() > 0.2 {
             self.eye = new_eye;
-            self.update_basis_vectors();
+            self.apply_orientation();
+        }
+    }
+
+    /// gira sobre el eje de visión (positivo = el lado derecho baja)
+    pub fn roll(&mut self, angle: f32) {
+        self.rotate(0.0, 0.0, angle);
+        self.apply_orientation();
+    }
+
+    /// quita el roll: vuelve a poner el arriba de la cámara hacia +Y de mundo
+    pub fn level(&mut self) {
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.update_basis_vectors();
+    }
+
+    /// vuelo libre: avanza en el marco de la cámara (adelante, derecha) y en vertical de mundo;
+    /// el centro viaja con el ojo para que la órbita siga funcionando al volver
+    pub fn fly(&mut self, fwd: f32, right: f32, up: f32) {
+        let d = self.forward * fwd + self.right * right + Vector3::new(0.0, up, 0.0);
+        self.eye += d;
+        self.center += d;
+    }
+
+    /// mirar alrededor sin moverse (yaw sobre Y de mundo, pitch sin límite)
+    pub fn look(&mut self, yaw: f32, pitch: f32) {
+        self.rotate(-yaw, pitch, 0.0);
+        self.apply_orientation();
+    }
+
+    /// desplaza ojo y centro de órbita en el plano de la pantalla
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let d = self.right * dx + self.up * dy;
+        self.eye += d;
+        self.center += d;
+    }
+
+    /// distancia focal (mm) equivalente al fov actual para el sensor configurado
+    pub fn focal_length(&self) -> f32 {
+        self.sensor_height / (2.0 * (self.fov_y * 0.5).tan())
+    }
+
+    pub fn set_focal_length(&mut self, mm: f32) {
+        self.fov_y = 2.0 * (self.sensor_height / (2.0 * mm.max(1.0))).atan();
+    }
+
+    /// zoom: cambia el fov (rad) dentro de un rango razonable; en ortográfica escala la vista
+    pub fn zoom(&mut self, dfov: f32) {
+        match &mut self.projection {
+            Projection::Orthographic { height } => *height = (*height * (1.0 + dfov * 2.0)).max(0.1),
+            Projection::Fisheye { fov } => *fov = (*fov + dfov).clamp(0.1, 2.0 * PI),
+            _ => self.fov_y = (self.fov_y + dfov).clamp(0.05, 3.0),
+        }
+    }
+
+    /// cicla perspectiva → ortográfica → ojo de pez → equirectangular
+    pub fn next_projection(&mut self) {
+        let dist = (self.center - self.eye).length();
+        self.projection = match self.projection {
+            // la vista ortográfica arranca abarcando lo mismo que la perspectiva en el centro
+            Projection::Perspective => Projection::Orthographic { height: 2.0 * dist * (self.fov_y * 0.5).tan() },
+            Projection::Orthographic { .. } => Projection::Fisheye { fov: PI },
+            Projection::Fisheye { .. } => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Perspective,
+        };
+    }
+
+    /// vista isométrica verdadera: ortográfica, 45° de giro y ~35.26° de elevación
+    pub fn set_isometric(&mut self) {
+        let dist = (self.center - self.eye).length();
+        let pitch = (1.0 / 2f32.sqrt()).atan();
+        let yaw = PI / 4.0;
+        let rel = Vector3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin()) * dist;
+        self.eye = self.center + rel;
+        self.up = Vector3::new(0.0, 1.0, 0.0);
+        self.update_basis_vectors();
+        if !matches!(self.projection, Projection::Orthographic { .. }) {
+            self.projection = Projection::Orthographic { height: 2.0 * dist * (self.fov_y * 0.5).tan() };
         }
     }
 
@@ -58,4 +239,273 @@ impl Camera {
             v.x * self.right.z + v.y * self.up.z - v.z * self.forward.z,
         )
     }
+
+    /// rayo (origen, dirección) en mundo por el punto (px,py) de una imagen w×h (admite fracciones);
+    /// None si el punto cae fuera de la proyección (esquinas del ojo de pez)
+    fn local_ray(&self, px: f32, py: f32, w: f32, h: f32) -> Option<(Vector3, Vector3)> {
+        let aspect = self.aspect.unwrap_or(w / h);
+        // coordenadas normalizadas de pantalla en [-1,1]
+        let nx = (2.0 * px) / w - 1.0;
+        let ny = -(2.0 * py) / h + 1.0;
+        match self.projection {
+            Projection::Perspective => {
+                let scale = (self.fov_y * 0.5).tan();
+                let rd_cam = Vector3::new(nx * aspect * scale + self.lens_shift, ny * scale, -1.0).normalized();
+                Some((self.eye, self.basis_change(&rd_cam).normalized()))
+            }
+            Projection::Orthographic { height } => {
+                let half = height * 0.5;
+                let o = self.eye + self.right * (nx * aspect * half) + self.up * (ny * half);
+                Some((o, self.forward))
+            }
+            Projection::Fisheye { fov } => {
+                // círculo inscrito en el lado corto
+                let (sx, sy) = if aspect >= 1.0 { (nx * aspect, ny) } else { (nx, ny / aspect) };
+                let r = (sx * sx + sy * sy).sqrt();
+                if r > 1.0 { return None; }
+                let theta = r * fov * 0.5;
+                let phi = sy.atan2(sx);
+                let rd_cam = Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos());
+                Some((self.eye, self.basis_change(&rd_cam).normalized()))
+            }
+            Projection::Equirectangular => {
+                // longitud en [-π, π] (centro = forward), latitud en [-π/2, π/2]
+                let lon = nx * PI;
+                let lat = ny * PI * 0.5;
+                // panorama nivelado con el horizonte (ignora pitch/roll) para visores VR
+                let world_up = Vector3::new(0.0, 1.0, 0.0);
+                let flat = Vector3::new(self.forward.x, 0.0, self.forward.z);
+                let fwd = if flat.length() > 1e-4 { flat.normalized() } else { Vector3::new(0.0, 0.0, -1.0) };
+                let right = fwd.cross(world_up);
+                let rd = right * (lat.cos() * lon.sin()) + world_up * lat.sin() + fwd * (lat.cos() * lon.cos());
+                Some((self.eye, rd.normalized()))
+            }
+        }
+    }
+
+    /// punto de la apertura para una muestra uniforme (u1,u2) ∈ [0,1)², en unidades del radio
+    fn lens_sample(&self, u1: f32, u2: f32) -> (f32, f32) {
+        if self.blades < 3 {
+            // disco: mapeo concéntrico de Shirley-Chiu
+            let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+            if a == 0.0 && b == 0.0 { return (0.0, 0.0); }
+            let (r, phi) = if a.abs() > b.abs() { (a, (PI / 4.0) * (b / a)) } else { (b, PI / 2.0 - (PI / 4.0) * (a / b)) };
+            return (r * phi.cos(), r * phi.sin());
+        }
+        // polígono regular: triángulo (centro, v_i, v_i+1) al azar, punto uniforme dentro
+        let n = self.blades as f32;
+        let k = (u1 * n).floor().min(n - 1.0);
+        let u1 = u1 * n - k; // reutiliza el resto como nueva muestra uniforme
+        let a0 = self.blade_rotation + k * 2.0 * PI / n;
+        let a1 = a0 + 2.0 * PI / n;
+        let (mut s, mut t) = (u1, u2);
+        if s + t > 1.0 { s = 1.0 - s; t = 1.0 - t; }
+        (s * a0.cos() + t * a1.cos(), s * a0.sin() + t * a1.sin())
+    }
+
+    /// rayo primario del pixel (px,py): origen en el plano cercano, dirección y diferenciales;
+    /// `lens` es una muestra uniforme en [0,1)² de la apertura (ignorada si es estenopeica)
+    pub fn primary_ray(&self, px: f32, py: f32, w: f32, h: f32, lens: (f32, f32)) -> Option<(Vector3, Vector3, RayDiff)> {
+        let (mut ro, mut rd) = self.local_ray(px, py, w, h)?;
+        // diferenciales por diferencia con los vecinos (si el vecino sale de la imagen, el del otro lado)
+        let neighbor = |dx: f32, dy: f32| {
+            self.local_ray(px + dx, py + dy, w, h).map(|(o, d)| (o - ro, d - rd))
+                .or_else(|| self.local_ray(px - dx, py - dy, w, h).map(|(o, d)| (ro - o, rd - d)))
+                .unwrap_or((Vector3::zero(), Vector3::zero()))
+        };
+        let (dodx, dddx) = neighbor(1.0, 0.0);
+        let (dody, dddy) = neighbor(0.0, 1.0);
+        let diff = RayDiff { dodx, dody, dddx, dddy };
+
+        let planar = matches!(self.projection, Projection::Perspective | Projection::Orthographic { .. });
+        if self.aperture > 0.0 && planar {
+            // lente delgada: todos los rayos del pixel convergen en el plano de enfoque
+            let focus = ro + rd * (self.focus_dist / rd.dot(self.forward).max(1e-4));
+            let (lx, ly) = self.lens_sample(lens.0, lens.1);
+            ro += (self.right * lx + self.up * ly) * self.aperture;
+            rd = (focus - ro).normalized();
+        }
+        // plano cercano: perpendicular a forward en las proyecciones planas, esfera en las angulares
+        let near_t = if planar { self.near / rd.dot(self.forward).max(1e-4) } else { self.near };
+        Some((ro + rd * near_t, rd, diff))
+    }
+
+    /// instante de una muestra u ∈ [0,1) dentro de la exposición
+    pub fn ray_time(&self, u: f32) -> f32 {
+        self.shutter_open + u * self.shutter
+    }
+
+    /// copia de la cámara en la fracción u de la exposición (entre la pose actual y `motion_end`)
+    pub fn at_shutter(&self, u: f32) -> Camera {
+        let mut c = self.clone();
+        if let Some((eye, orientation)) = self.motion_end {
+            c.eye = self.eye.lerp(eye, u);
+            c.orientation = slerp_shortest(self.orientation, orientation, u);
+            c.apply_orientation();
+        }
+        c
+    }
+
+    /// cámara de un ojo: ejes paralelos separados `interocular` y frustum desplazado
+    /// (off-axis) para que lo que está a `convergence` quede sin paralaje
+    pub fn eye_view(&self, eye: Eye) -> Camera {
+        let side = match eye { Eye::Left => -0.5, Eye::Right => 0.5 } * self.interocular;
+        let mut c = self.clone();
+        c.eye += self.right * side;
+        c.center += self.right * side;
+        c.lens_shift = self.lens_shift - side / self.convergence.max(1e-3);
+        if let Some((e, q)) = self.motion_end {
+            c.motion_end = Some((e + Vector3::new(1.0, 0.0, 0.0).rotate_by(q) * side, q));
+        }
+        c.stereo = Stereo::Off;
+        c
+    }
+
+    /// cicla apagado → lado a lado → arriba/abajo → anaglifo
+    pub fn next_stereo(&mut self) {
+        self.stereo = match self.stereo {
+            Stereo::Off => Stereo::SideBySide,
+            Stereo::SideBySide => Stereo::TopBottom,
+            Stereo::TopBottom => Stereo::Anaglyph,
+            Stereo::Anaglyph => Stereo::Off,
+        };
+    }
+
+    /// rayo por el centro de la pantalla (para el autofoco)
+    pub fn center_ray(&self) -> (Vector3, Vector3) {
+        (self.eye, self.forward)
+    }
+
+    /// enfoca a `t` unidades sobre el rayo central
+    pub fn focus_at(&mut self, t: f32) {
+        self.focus_dist = t.max(self.near + 0.01);
+    }
+
+    /// vuelca posición y óptica en un archivo de escena (claves `camera.*`)
+    pub fn write_to(&self, sf: &mut SceneFile) {
+        sf.set_v3("camera.eye", self.eye);
+        sf.set_v3("camera.center", self.center);
+        sf.set_v3("camera.up", self.up);
+        sf.set_f32("camera.fov", self.fov_y.to_degrees());
+        sf.set_f32("camera.near", self.near);
+        sf.set_f32("camera.sensor", self.sensor_height);
+        sf.set_f32("camera.aperture", self.aperture);
+        sf.set_f32("camera.focus", self.focus_dist);
+        sf.set_f32("camera.blades", self.blades as f32);
+        sf.set_f32("camera.blade_rotation", self.blade_rotation.to_degrees());
+        sf.set_f32("camera.autofocus", if self.autofocus { 1.0 } else { 0.0 });
+        sf.set_f32("camera.shutter", self.shutter);
+        let stereo = match self.stereo {
+            Stereo::Off => "off", Stereo::SideBySide => "side-by-side",
+            Stereo::TopBottom => "top-bottom", Stereo::Anaglyph => "anaglyph",
+        };
+        sf.set("camera.stereo", &[stereo.to_string()]);
+        sf.set_f32("camera.interocular", self.interocular);
+        sf.set_f32("camera.convergence", self.convergence);
+        let proj = match self.projection {
+            Projection::Perspective => vec!["perspective".to_string()],
+            Projection::Orthographic { height } => vec!["orthographic".to_string(), height.to_string()],
+            Projection::Fisheye { fov } => vec!["fisheye".to_string(), fov.to_degrees().to_string()],
+            Projection::Equirectangular => vec!["equirectangular".to_string()],
+        };
+        sf.set("camera.projection", &proj);
+        let mode = match self.mode { CameraMode::Orbit => "orbit", CameraMode::Fly => "fly" };
+        sf.set("camera.mode", &[mode.to_string()]);
+        match self.aspect {
+            Some(a) => sf.set_f32("camera.aspect", a),
+            None => sf.set("camera.aspect", &["auto".to_string()]),
+        }
+    }
+
+    /// lee las claves `camera.*` presentes (las que falten se quedan como están);
+    /// `camera.focal` (mm) tiene prioridad sobre `camera.fov` (grados)
+    pub fn read_from(&mut self, sf: &SceneFile) {
+        if let Some(v) = sf.get_v3("camera.eye") { self.eye = v; }
+        if let Some(v) = sf.get_v3("camera.center") { self.center = v; }
+        if let Some(v) = sf.get_v3("camera.up") { self.up = v; }
+        if let Some(v) = sf.get_f32("camera.sensor") { self.sensor_height = v; }
+        if let Some(v) = sf.get_f32("camera.fov") { self.fov_y = v.to_radians(); }
+        if let Some(v) = sf.get_f32("camera.focal") { self.set_focal_length(v); }
+        if let Some(v) = sf.get_f32("camera.near") { self.near = v.max(0.0); }
+        if let Some(v) = sf.get_f32("camera.aperture") { self.aperture = v.max(0.0); }
+        if let Some(v) = sf.get_f32("camera.focus") { self.focus_dist = v.max(0.01); }
+        if let Some(v) = sf.get_f32("camera.blades") { self.blades = v.max(0.0) as u32; }
+        if let Some(v) = sf.get_f32("camera.blade_rotation") { self.blade_rotation = v.to_radians(); }
+        if let Some(v) = sf.get_f32("camera.autofocus") { self.autofocus = v != 0.0; }
+        if let Some(v) = sf.get_f32("camera.shutter") { self.shutter = v.max(0.0); }
+        match sf.get_str("camera.stereo") {
+            Some("off") => self.stereo = Stereo::Off,
+            Some("side-by-side") => self.stereo = Stereo::SideBySide,
+            Some("top-bottom") => self.stereo = Stereo::TopBottom,
+            Some("anaglyph") => self.stereo = Stereo::Anaglyph,
+            _ => {}
+        }
+        if let Some(v) = sf.get_f32("camera.interocular") { self.interocular = v.max(0.0); }
+        if let Some(v) = sf.get_f32("camera.convergence") { self.convergence = v.max(0.01); }
+        if let Some(p) = sf.get("camera.projection") {
+            let arg = |def: f32| p.get(1).and_then(|v| v.parse().ok()).unwrap_or(def);
+            match p.first().map(String::as_str) {
+                Some("perspective") => self.projection = Projection::Perspective,
+                Some("orthographic") => self.projection = Projection::Orthographic { height: arg(4.0) },
+                Some("fisheye") => self.projection = Projection::Fisheye { fov: arg(180.0).to_radians() },
+                Some("equirectangular") => self.projection = Projection::Equirectangular,
+                _ => {}
+            }
+        }
+        match sf.get_str("camera.mode") {
+            Some("orbit") => self.mode = CameraMode::Orbit,
+            Some("fly") => self.mode = CameraMode::Fly,
+            _ => {}
+        }
+        if sf.get_str("camera.aspect").is_some() { self.aspect = sf.get_f32("camera.aspect"); }
+        self.update_basis_vectors();
+    }
+}
+
+/// cuaternión de la rotación cuyas columnas son (x, y, z) (base ortonormal derecha)
+fn quat_from_basis(x: Vector3, y: Vector3, z: Vector3) -> Quaternion {
+    let trace = x.x + y.y + z.z;
+    let q = if trace > 0.0 {
+        let s = 0.5 / (trace + 1.0).sqrt();
+        Quaternion::new((y.z - z.y) * s, (z.x - x.z) * s, (x.y - y.x) * s, 0.25 / s)
+    } else if x.x > y.y && x.x > z.z {
+        let s = 2.0 * (1.0 + x.x - y.y - z.z).sqrt();
+        Quaternion::new(0.25 * s, (y.x + x.y) / s, (z.x + x.z) / s, (y.z - z.y) / s)
+    } else if y.y > z.z {
+        let s = 2.0 * (1.0 + y.y - x.x - z.z).sqrt();
+        Quaternion::new((y.x + x.y) / s, 0.25 * s, (z.y + y.z) / s, (z.x - x.z) / s)
+    } else {
+        let s = 2.0 * (1.0 + z.z - x.x - y.y).sqrt();
+        Quaternion::new((z.x + x.z) / s, (z.y + y.z) / s, 0.25 * s, (x.y - y.x) / s)
+    };
+    q.normalized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector3, b: Vector3) -> bool { (a - b).length() < 1e-4 }
+
+    #[test]
+    fn yaw_pitch_and_back_returns_up_without_roll() {
+        let mut c = Camera::new(Vector3::new(0.0, 1.0, 5.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+        let (up, fwd) = (c.up, c.forward);
+        for (y, p) in [(0.3, 0.2), (-0.7, 0.4), (1.1, -0.5)] {
+            c.look(y, p);
+            // la derecha sigue horizontal: el horizonte no se inclina
+            assert!(c.right.y.abs() < 1e-4, "roll acumulado: {:?}", c.right);
+        }
+        for (y, p) in [(1.1, -0.5), (-0.7, 0.4), (0.3, 0.2)] { c.look(-y, -p); }
+        assert!(close(c.up, up) && close(c.forward, fwd), "up {:?} forward {:?}", c.up, c.forward);
+    }
+
+    #[test]
+    fn orbit_circles_world_y() {
+        let mut c = Camera::new(Vector3::new(0.0, 2.0, 5.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+        let (h, r) = (c.eye.y, (c.eye - c.center).length());
+        for _ in 0..10 { c.orbit(0.3, 0.0); }
+        assert!((c.eye.y - h).abs() < 1e-3 && ((c.eye - c.center).length() - r).abs() < 1e-3);
+        assert!(c.right.y.abs() < 1e-4);
+    }
 }
@@ -0,0 +1,190 @@
+use raylib::prelude::*;
+
+/// luminancia relativa de un color lineal (Rec.709)
+pub fn luminance(c: Vector3) -> f32 { 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z }
+
+/// XYZ → RGB lineal (primarios sRGB/Rec.709, blanco D65)
+pub fn xyz_to_rgb(xyz: Vector3) -> Vector3 {
+    Vector3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// funciones de igualación CIE 1931 (ajuste multi-lóbulo de Wyman, Sloan y Shirley 2013), λ en nm
+pub fn cie_xyz(lambda: f32) -> Vector3 {
+    let g = |mu: f32, s1: f32, s2: f32| {
+        let t = (lambda - mu) / if lambda < mu { s1 } else { s2 };
+        (-0.5 * t * t).exp()
+    };
+    Vector3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// color de un cuerpo negro a `kelvin` (Planck integrado con las CIE), con el canal mayor en 1
+pub fn blackbody(kelvin: f32) -> Vector3 {
+    const C2: f32 = 1.4388e7; // hc/k en nm·K
+    let mut xyz = Vector3::zero();
+    let mut lambda: f32 = 380.0;
+    while lambda <= 780.0 {
+        // la constante de Planck se cancela al normalizar
+        let b = 1.0 / (lambda.powi(5) * ((C2 / (lambda * kelvin)).exp() - 1.0));
+        xyz += cie_xyz(lambda) * b;
+        lambda += 5.0;
+    }
+    let rgb = xyz_to_rgb(xyz);
+    let rgb = Vector3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0));
+    let m = rgb.x.max(rgb.y).max(rgb.z);
+    if m > 0.0 { rgb / m } else { rgb }
+}
+
+// === modo espectral ===
+
+/// rango de longitudes de onda que se muestrea (nm)
+pub const LAMBDA_MIN: f32 = 380.0;
+pub const LAMBDA_MAX: f32 = 720.0;
+
+/// temperatura del espectro con que se aproxima el blanco D65
+const WHITE_KELVIN: f32 = 6504.0;
+
+/// tablas del modo espectral, se calculan una sola vez
+struct SpectralTables {
+    white: Vector3,           // color del blanco (D65) antes de normalizar
+    inv_basis: [[f32; 3]; 3], // color de cada función base bajo el blanco → coeficientes
+}
+
+/// promedio de f(λ) en el rango (punto medio, pasos de 1 nm)
+fn average(f: impl Fn(f32) -> Vector3) -> Vector3 {
+    let n = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+    (0..n).fold(Vector3::zero(), |acc, i| acc + f(LAMBDA_MIN + i as f32 + 0.5)) / n as f32
+}
+
+fn smoothstep(a: f32, b: f32, x: f32) -> f32 {
+    let t = ((x - a) / (b - a)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// base suave para subir RGB a espectro: rojo, verde y azul solapados que suman 1 en todo el rango
+fn basis(lambda: f32) -> Vector3 {
+    let b = 1.0 - smoothstep(470.0, 530.0, lambda);
+    let r = smoothstep(560.0, 620.0, lambda);
+    Vector3::new(r, 1.0 - r - b, b)
+}
+
+/// espectro de Planck sin normalizar (en unidades de 500 nm para no salir del rango de f32)
+fn planck(lambda: f32, kelvin: f32) -> f32 {
+    const C2: f32 = 1.4388e7; // hc/k en nm·K
+    let l = lambda / 500.0;
+    1.0 / (l.powi(5) * ((C2 / (lambda * kelvin)).exp() - 1.0))
+}
+
+fn tables() -> &'static SpectralTables {
+    static TABLES: std::sync::OnceLock<SpectralTables> = std::sync::OnceLock::new();
+    TABLES.get_or_init(|| {
+        let white = average(|l| xyz_to_rgb(cie_xyz(l)) * planck(l, WHITE_KELVIN));
+        let lit = |l: f32| {
+            let c = xyz_to_rgb(cie_xyz(l)) * planck(l, WHITE_KELVIN);
+            Vector3::new(c.x / white.x, c.y / white.y, c.z / white.z)
+        };
+        // columna j = color de la función base j iluminada por el blanco
+        let cols = [
+            average(|l| lit(l) * basis(l).x),
+            average(|l| lit(l) * basis(l).y),
+            average(|l| lit(l) * basis(l).z),
+        ];
+        let m = [
+            [cols[0].x, cols[1].x, cols[2].x],
+            [cols[0].y, cols[1].y, cols[2].y],
+            [cols[0].z, cols[1].z, cols[2].z],
+        ];
+        SpectralTables { white, inv_basis: invert3(m) }
+    })
+}
+
+fn invert3(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let c = |r: usize, k: usize| {
+        let (r0, r1) = ((r + 1) % 3, (r + 2) % 3);
+        let (k0, k1) = ((k + 1) % 3, (k + 2) % 3);
+        m[r0][k0] * m[r1][k1] - m[r0][k1] * m[r1][k0]
+    };
+    let det = m[0][0] * c(0, 0) + m[0][1] * c(0, 1) + m[0][2] * c(0, 2);
+    let mut inv = [[0.0; 3]; 3];
+    for (i, row) in inv.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() { *v = c(j, i) / det; }
+    }
+    inv
+}
+
+/// aporte RGB de una muestra de radiancia 1 en `lambda` (λ uniforme en el rango);
+/// balanceado para que el blanco (`rgb_to_illuminant` de (1,1,1)) dé (1,1,1)
+pub fn wavelength_to_rgb(lambda: f32) -> Vector3 {
+    let w = tables().white;
+    let c = xyz_to_rgb(cie_xyz(lambda));
+    Vector3::new(c.x / w.x, c.y / w.y, c.z / w.z)
+}
+
+/// reflectancia suave en `lambda` que bajo luz blanca se ve del color `rgb`
+/// (el blanco queda plano en 1; los primarios puros se recortan apenas en 0)
+pub fn rgb_to_reflectance(rgb: Vector3, lambda: f32) -> f32 {
+    let m = &tables().inv_basis;
+    let k = |i: usize| m[i][0] * rgb.x + m[i][1] * rgb.y + m[i][2] * rgb.z;
+    let b = basis(lambda);
+    (k(0) * b.x + k(1) * b.y + k(2) * b.z).max(0.0)
+}
+
+/// radiancia en `lambda` de una luz del color `rgb`: la reflectancia por el espectro del blanco
+pub fn rgb_to_illuminant(rgb: Vector3, lambda: f32) -> f32 {
+    rgb_to_reflectance(rgb, lambda) * planck(lambda, WHITE_KELVIN)
+}
+
+/// espectro de Planck a `kelvin` con la misma escala que `blackbody` (canal mayor de su color en 1)
+#[derive(Clone, Copy, Debug)]
+pub struct BlackbodySpectrum {
+    kelvin: f32,
+    norm: f32,
+}
+
+impl BlackbodySpectrum {
+    pub fn new(kelvin: f32) -> Self {
+        let rgb = average(|l| wavelength_to_rgb(l) * planck(l, kelvin));
+        let m = rgb.x.max(rgb.y).max(rgb.z);
+        Self { kelvin, norm: if m > 0.0 { 1.0 / m } else { 0.0 } }
+    }
+
+    pub fn at(&self, lambda: f32) -> f32 { planck(lambda, self.kelvin) * self.norm }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn white_reflectance_is_flat() {
+        for l in (380..720).step_by(5) {
+            let r = rgb_to_reflectance(Vector3::one(), l as f32);
+            assert!((r - 1.0).abs() < 1e-4, "λ {}: {}", l, r);
+        }
+    }
+
+    #[test]
+    fn illuminant_round_trip_recovers_rgb() {
+        for c in [(1.0, 1.0, 1.0), (0.5, 0.5, 0.5), (0.8, 0.3, 0.2), (0.2, 0.6, 0.3), (0.9, 0.85, 0.7), (0.1, 0.2, 0.9)] {
+            let c = Vector3::new(c.0, c.1, c.2);
+            let rgb = average(|l| wavelength_to_rgb(l) * rgb_to_illuminant(c, l));
+            assert!((rgb - c).length() < 2e-3, "{:?} → {:?}", c, rgb);
+        }
+    }
+
+    #[test]
+    fn blackbody_spectrum_matches_rgb_blackbody() {
+        for k in [2700.0, 4000.0, 6500.0, 8000.0] {
+            let s = BlackbodySpectrum::new(k);
+            let rgb = average(|l| wavelength_to_rgb(l) * s.at(l));
+            assert!((rgb - blackbody(k)).length() < 0.1, "{} K: {:?} vs {:?}", k, rgb, blackbody(k));
+        }
+    }
+}
@@ -1,26 +1,47 @@
 use raylib::prelude::*;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray_intersect::{Intersect, RayIntersect, ortho_basis};
 use crate::material::Material;
+use crate::texture::UvTransform;
 
 /// Cubo axis-aligned (AABB)
 pub struct Cube {
     pub min: Vector3,
     pub max: Vector3,
     pub mat: Material,
-    pub uv_scale: f32, // <-- cuántas veces repetir la textura (uniforme)
+    pub uv: UvTransform, // escala/rotación/desplazamiento de uv
+    pub world_uv: bool,  // uv en unidades de mundo (la escala es repeticiones por unidad)
 }
 
 impl Cube {
     pub fn from_center_size(center: Vector3, size: Vector3, mat: Material) -> Self {
         let half = size * 0.5;
-        Self { min: center - half, max: center + half, mat, uv_scale: 1.0 }
+        Self { min: center - half, max: center + half, mat, uv: UvTransform::identity(), world_uv: false }
     }
 
     /// helper opcional para setear tiling
     pub fn with_tiling(mut self, uv_scale: f32) -> Self {
-        self.uv_scale = uv_scale.max(0.001);
+        let s = uv_scale.max(0.001);
+        self.uv.scale = (s, s);
         self
     }
+
+    /// transformación completa de uv (desplazamiento, rotación, escala por eje)
+    pub fn with_uv_transform(mut self, uv: UvTransform) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    /// tiling en mundo: misma densidad de textura sin importar el tamaño de la cara
+    pub fn with_world_tiling(mut self, per_unit_u: f32, per_unit_v: f32) -> Self {
+        self.world_uv = true;
+        self.uv.scale = (per_unit_u.max(0.001), per_unit_v.max(0.001));
+        self
+    }
+
+    /// esfera (centro, radio) que envuelve la caja
+    pub fn bounding_sphere(&self) -> (Vector3, f32) {
+        ((self.min + self.max) * 0.5, (self.max - self.min).length() * 0.5)
+    }
 }
 
 impl RayIntersect for Cube {
@@ -39,7 +60,13 @@ impl RayIntersect for Cube {
         t1 = (self.min.z - ro.z) * inv.z; t2 = (self.max.z - ro.z) * inv.z;
         tmin = tmin.max(t1.min(t2)); tmax = tmax.min(t1.max(t2));
 
-        let t = if tmax >= tmin.max(0.0) { tmin.max(0.0) } else { f32::INFINITY };
+        // desde dentro, las cajas que se pueden atravesar (recortes por alfa, vidrio) dan la cara de
+        // salida (tmax): el rayo que sigue detrás de un texel recortado llega a la cara de atrás y el
+        // refractado encuentra por dónde sale; las opacas siguen devolviendo el origen (t = 0)
+        let see_through = self.mat.alpha_map.is_some() || self.mat.albedo[3] > 0.0;
+        let t = if tmax >= tmin.max(0.0) {
+            if tmin > 0.0 || !see_through { tmin.max(0.0) } else { tmax }
+        } else { f32::INFINITY };
         if !t.is_finite() { return Intersect::empty(); }
 
         let p = *ro + *rd * t;
@@ -48,34 +75,104 @@ impl RayIntersect for Cube {
         let eps = 1e-3;
         let mut n = Vector3::zero();
         let mut uv = (0.0, 0.0);
+        // tangentes = dirección en la que crecen u y v sobre cada cara
+        let ex = Vector3::new(1.0, 0.0, 0.0);
+        let ey = Vector3::new(0.0, 1.0, 0.0);
+        let ez = Vector3::new(0.0, 0.0, 1.0);
+        let size = self.max - self.min;
+        let (tan, bit);
+        let (du, dv); // extensión de la cara a lo largo de u y v
         // X faces
         if (p.x - self.min.x).abs() < eps { 
             n = Vector3::new(-1.0, 0.0, 0.0); 
+            (tan, bit) = (ez, ey); (du, dv) = (size.z, size.y);
             uv = ((p.z - self.min.z)/(self.max.z-self.min.z), (p.y - self.min.y)/(self.max.y-self.min.y)); 
         } else if (p.x - self.max.x).abs() < eps { 
             n = Vector3::new( 1.0, 0.0, 0.0); 
+            (tan, bit) = (ez, ey); (du, dv) = (size.z, size.y);
             uv = ((p.z - self.min.z)/(self.max.z-self.min.z), (p.y - self.min.y)/(self.max.y-self.min.y)); 
         }
         // Y faces
         else if (p.y - self.min.y).abs() < eps { 
             n = Vector3::new(0.0,-1.0, 0.0); 
+            (tan, bit) = (ex, ez); (du, dv) = (size.x, size.z);
             uv = ((p.x - self.min.x)/(self.max.x-self.min.x), (p.z - self.min.z)/(self.max.z-self.min.z)); 
         } else if (p.y - self.max.y).abs() < eps { 
             n = Vector3::new(0.0, 1.0, 0.0); 
+            (tan, bit) = (ex, ez); (du, dv) = (size.x, size.z);
             uv = ((p.x - self.min.x)/(self.max.x-self.min.x), (p.z - self.min.z)/(self.max.z-self.min.z)); 
         }
         // Z faces
         else if (p.z - self.min.z).abs() < eps { 
             n = Vector3::new(0.0, 0.0,-1.0); 
+            (tan, bit) = (ex, ey); (du, dv) = (size.x, size.y);
             uv = ((p.x - self.min.x)/(self.max.x-self.min.x), (p.y - self.min.y)/(self.max.y-self.min.y)); 
         } else { 
             n = Vector3::new(0.0, 0.0, 1.0); 
+            (tan, bit) = (ex, ey); (du, dv) = (size.x, size.y);
             uv = ((p.x - self.min.x)/(self.max.x-self.min.x), (p.y - self.min.y)/(self.max.y-self.min.y)); 
         }
 
-        // aplica tiling (repetición)
-        let uv = (uv.0 * self.uv_scale, uv.1 * self.uv_scale);
+        // en modo mundo la cara mide du × dv en vez de 1 × 1
+        let uv = if self.world_uv { (uv.0 * du, uv.1 * dv) } else { uv };
+        let (du, dv) = if self.world_uv { (1.0, 1.0) } else { (du, dv) };
+
+        // aplica tiling/rotación/desplazamiento
+        let uv = self.uv.apply(uv.0, uv.1);
+
+        // M = J · diag(1/du, 1/dv) lleva (tan, bit) del mundo a uv; sus filas son los gradientes
+        // y las columnas de M⁻¹ son dP/du y dP/dv (tangentes para normal mapping)
+        let [[a, b], [c, d]] = self.uv.jacobian();
+        let (a, b, c, d) = (a / du, b / dv, c / du, d / dv);
+        let grad_u = tan * a + bit * b;
+        let grad_v = tan * c + bit * d;
+        let det = a * d - b * c;
+        // escala nula en algún eje (campos puestos a mano): M no se invierte, tangentes cualquiera
+        let (dpdu, dpdv) = if det.abs() > 1e-12 {
+            (((tan * d - bit * c) / det).normalized(), ((bit * a - tan * b) / det).normalized())
+        } else {
+            ortho_basis(&n)
+        };
 
         Intersect::new(p, n, t, self.mat, uv)
+            .with_tangents(dpdu, dpdv)
+            .with_uv_gradients(grad_u, grad_v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cube() -> Cube { Cube::from_center_size(Vector3::zero(), Vector3::one(), Material::black()) }
+
+    #[test]
+    fn outside_ray_hits_entry_face() {
+        let hit = unit_cube().ray_intersect(&Vector3::new(0.0, 0.0, 3.0), &Vector3::new(0.0, 0.0, -1.0));
+        assert!(hit.hit && (hit.t - 2.5).abs() < 1e-5);
+        assert_eq!((hit.normal.x, hit.normal.y, hit.normal.z), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn zero_uv_scale_keeps_tangents_finite() {
+        let mut cube = unit_cube();
+        cube.uv.scale = (0.0, 1.0);
+        let hit = cube.ray_intersect(&Vector3::new(0.2, 0.1, 3.0), &Vector3::new(0.0, 0.0, -1.0));
+        for v in [hit.tangent, hit.bitangent] {
+            assert!(v.x.is_finite() && v.y.is_finite() && v.z.is_finite() && (v.length() - 1.0).abs() < 1e-4);
+        }
+        let t = UvTransform::new((0.0, 0.0), 0.3, (0.0, -2.0));
+        assert!(t.scale.0 > 0.0 && t.scale.1 == -2.0);
+    }
+
+    #[test]
+    fn inside_ray_hits_exit_face_only_when_see_through() {
+        let (ro, rd) = (Vector3::new(0.0, 0.1, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(unit_cube().ray_intersect(&ro, &rd).t, 0.0);
+
+        let glass = Material::new(Vector3::one(), 1.0, [0.0, 0.0, 0.0, 0.9], 1.5);
+        let hit = Cube::from_center_size(Vector3::zero(), Vector3::one(), glass).ray_intersect(&ro, &rd);
+        assert!(hit.hit && (hit.t - 0.5).abs() < 1e-5, "t = {}", hit.t);
+        assert_eq!((hit.normal.x, hit.normal.y, hit.normal.z), (1.0, 0.0, 0.0));
     }
 }
@@ -0,0 +1,67 @@
+use raylib::prelude::*;
+
+/// índice de refracción que depende de la longitud de onda
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    /// n(λ) = A + B/λ² (λ en µm)
+    Cauchy { a: f32, b: f32 },
+    /// n²(λ) = 1 + Σ Bi·λ²/(λ² - Ci) (λ en µm, Ci en µm²)
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+/// longitudes de onda con que se refracta cada canal R, G, B (nm)
+pub const RGB_WAVELENGTHS: [f32; 3] = [630.0, 532.0, 465.0];
+
+/// máscara del canal `c` (0 = R, 1 = G, 2 = B)
+pub fn channel_mask(c: usize) -> Vector3 {
+    match c { 0 => Vector3::new(1.0, 0.0, 0.0), 1 => Vector3::new(0.0, 1.0, 0.0), _ => Vector3::new(0.0, 0.0, 1.0) }
+}
+
+impl Dispersion {
+    /// vidrio crown común (n ≈ 1.517)
+    pub const BK7: Dispersion = Dispersion::Sellmeier {
+        b: [1.039_612, 0.231_792_3, 1.010_469_5],
+        c: [0.006_000_7, 0.020_017_91, 103.560_65],
+    };
+    /// flint denso (n ≈ 1.78, mucha dispersión): prismas
+    pub const SF11: Dispersion = Dispersion::Sellmeier {
+        b: [1.737_597, 0.313_747_3, 1.898_781],
+        c: [0.013_188_71, 0.062_306_81, 155.236_3],
+    };
+
+    pub fn ior(&self, lambda_nm: f32) -> f32 {
+        let l = lambda_nm * 1e-3;
+        let l2 = l * l;
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                (1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f32>()).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bk7_and_sf11_match_catalogue_values() {
+        // líneas de Fraunhofer F, d y C
+        for (lambda, n) in [(486.13, 1.5224), (587.56, 1.5168), (656.27, 1.5143)] {
+            let got = Dispersion::BK7.ior(lambda);
+            assert!((got - n).abs() < 2e-4, "BK7 a {} nm: {}", lambda, got);
+        }
+        assert!((Dispersion::SF11.ior(587.56) - 1.7847).abs() < 5e-4);
+        let cauchy = Dispersion::Cauchy { a: 1.5046, b: 0.0042 };
+        assert!((cauchy.ior(587.56) - 1.5168).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ior_falls_as_wavelength_grows() {
+        for d in [Dispersion::BK7, Dispersion::SF11, Dispersion::Cauchy { a: 1.5046, b: 0.0042 }] {
+            let n: Vec<f32> = (38..=72).map(|i| d.ior(i as f32 * 10.0)).collect();
+            assert!(n.windows(2).all(|w| w[1] < w[0]), "{:?}", d);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use crate::skybox::Skybox;
+
+/// luz que llega del infinito en la dirección `dir` (cielo, mapa de entorno)
+pub trait Environment: Sync {
+    fn sample(&self, dir: Vector3) -> Vector3;
+}
+
+impl Environment for Skybox {
+    fn sample(&self, dir: Vector3) -> Vector3 { Skybox::sample(self, dir) }
+}
+
+/// mapa de entorno equirectangular (lat-long) en color lineal; admite valores > 1 (HDR)
+pub struct EquirectEnv {
+    pub w: usize,
+    pub h: usize,
+    data: Vec<Vector3>,
+    pub rotation: f32,  // giro alrededor de +Y (rad)
+    pub intensity: f32, // multiplicador
+}
+
+impl EquirectEnv {
+    /// `data` fila por fila, w·h texels; un mapa vacío no se puede muestrear
+    pub fn from_data(w: usize, h: usize, data: Vec<Vector3>) -> Self {
+        assert!(w > 0 && h > 0 && data.len() == w * h, "mapa de entorno {}x{} con {} texels", w, h, data.len());
+        Self { w, h, data, rotation: 0.0, intensity: 1.0 }
+    }
+
+    /// imagen LDR (png, jpg...) tal cual, en [0,1]
+    pub fn from_image(img: &Image) -> Self {
+        let data = img.get_image_data().iter()
+            .map(|c| Vector3::new(c.r as f32, c.g as f32, c.b as f32) / 255.0)
+            .collect();
+        Self::from_data(img.width() as usize, img.height() as usize, data)
+    }
+
+    /// `.hdr` de Radiance (RGBE, plano o con RLE por canal); solo orientación `-Y h +X w`
+    pub fn load_hdr(path: &str) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let (w, h, data) = decode_rgbe(&bytes).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: .hdr inválido o no soportado", path))
+        })?;
+        Ok(Self::from_data(w, h, data))
+    }
+
+    /// `.hdr` por extensión; cualquier otra cosa pasa por raylib como LDR
+    pub fn load(path: &str) -> io::Result<Self> {
+        if path.to_ascii_lowercase().ends_with(".hdr") { return Self::load_hdr(path); }
+        let img = Image::load_image(path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {:?}", path, e)))?;
+        if img.width() <= 0 || img.height() <= 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: imagen vacía", path)));
+        }
+        Ok(Self::from_image(&img))
+    }
+
+    pub fn with_rotation(mut self, radians: f32) -> Self {
+        self.rotation = radians;
+        self
+    }
+
+    pub fn with_intensity(mut self, k: f32) -> Self {
+        self.intensity = k;
+        self
+    }
+
+    fn texel(&self, x: usize, y: usize) -> Vector3 { self.data[y * self.w + x] }
+}
+
+/// dirección del punto (u,v) de un mapa lat-long (inversa de la búsqueda de `EquirectEnv`, sin giro)
+pub fn latlong_dir(u: f32, v: f32) -> Vector3 {
+    let lon = (u - 0.5) * 2.0 * PI;
+    let theta = v * PI;
+    Vector3::new(theta.sin() * lon.sin(), theta.cos(), -theta.sin() * lon.cos())
+}
+
+impl Environment for EquirectEnv {
+    /// u = longitud (0.5 = -Z, crece hacia +X), v = 0 arriba (+Y)
+    fn sample(&self, dir: Vector3) -> Vector3 {
+        let d = dir.normalized();
+        let lon = d.x.atan2(-d.z) - self.rotation;
+        let u = (0.5 + lon / (2.0 * PI)).rem_euclid(1.0);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+
+        // bilineal: repite en u (costura a 360°), recorta en v (polos)
+        let fx = u * self.w as f32 - 0.5;
+        let fy = (v * self.h as f32 - 0.5).clamp(0.0, (self.h - 1) as f32);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let xa = (x0 as isize).rem_euclid(self.w as isize) as usize;
+        let xb = (xa + 1) % self.w;
+        let ya = y0 as usize;
+        let yb = (ya + 1).min(self.h - 1);
+        let top = self.texel(xa, ya).lerp(self.texel(xb, ya), tx);
+        let bottom = self.texel(xa, yb).lerp(self.texel(xb, yb), tx);
+        top.lerp(bottom, ty) * self.intensity
+    }
+}
+
+/// decodifica un archivo RGBE completo → (ancho, alto, pixeles lineales)
+fn decode_rgbe(bytes: &[u8]) -> Option<(usize, usize, Vec<Vector3>)> {
+    // cabecera: líneas de texto hasta una vacía, luego la resolución
+    let mut pos = 0;
+    let mut line = || -> Option<&str> {
+        let end = bytes[pos..].iter().position(|&b| b == b'\n')? + pos;
+        let s = std::str::from_utf8(&bytes[pos..end]).ok()?;
+        pos = end + 1;
+        Some(s.trim_end())
+    };
+    let magic = line()?;
+    if !magic.starts_with("#?") { return None; }
+    loop {
+        let l = line()?;
+        if l.is_empty() { break; }
+        if let Some(fmt) = l.strip_prefix("FORMAT=") && fmt != "32-bit_rle_rgbe" { return None; }
+    }
+    let res: Vec<&str> = line()?.split_whitespace().collect();
+    if res.len() != 4 || res[0] != "-Y" || res[2] != "+X" { return None; }
+    let (h, w): (usize, usize) = (res[1].parse().ok()?, res[3].parse().ok()?);
+    if w == 0 || h == 0 { return None; }
+
+    let mut data = Vec::with_capacity(w * h);
+    let mut scan = vec![[0u8; 4]; w];
+    for _ in 0..h {
+        let rest = bytes.get(pos..)?;
+        if (8..0x8000).contains(&w) && rest.len() >= 4 && rest[0] == 2 && rest[1] == 2 && rest[2] & 0x80 == 0 {
+            // RLE nuevo: cada canal por separado
+            if ((rest[2] as usize) << 8 | rest[3] as usize) != w { return None; }
+            pos += 4;
+            for ch in 0..4 {
+                let mut x = 0;
+                while x < w {
+                    let count = *bytes.get(pos)? as usize;
+                    pos += 1;
+                    if count > 128 {
+                        let n = count - 128;
+                        let val = *bytes.get(pos)?;
+                        pos += 1;
+                        if x + n > w { return None; }
+                        for px in &mut scan[x..x + n] { px[ch] = val; }
+                        x += n;
+                    } else {
+                        if count == 0 || x + count > w { return None; }
+                        for (i, px) in scan[x..x + count].iter_mut().enumerate() { px[ch] = *bytes.get(pos + i)?; }
+                        pos += count;
+                        x += count;
+                    }
+                }
+            }
+        } else {
+            // sin compresión
+            for px in scan.iter_mut() {
+                px.copy_from_slice(bytes.get(pos..pos + 4)?);
+                pos += 4;
+            }
+        }
+        data.extend(scan.iter().map(|&[r, g, b, e]| {
+            if e == 0 { return Vector3::zero(); }
+            let f = (e as f32 - 136.0).exp2();
+            Vector3::new((r as f32 + 0.5) * f, (g as f32 + 0.5) * f, (b as f32 + 0.5) * f)
+        }));
+    }
+    Some((w, h, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(w: usize, h: usize) -> Vec<u8> {
+        format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", h, w).into_bytes()
+    }
+
+    #[test]
+    fn decodes_flat_rgbe() {
+        let mut bytes = header(2, 1);
+        // e = 129 → 2^(129-136) = 1/128; e = 0 → negro
+        bytes.extend([128, 64, 0, 129, 200, 200, 200, 0]);
+        let (w, h, data) = decode_rgbe(&bytes).unwrap();
+        assert_eq!((w, h), (2, 1));
+        assert_eq!((data[0].x, data[0].y, data[0].z), (128.5 / 128.0, 64.5 / 128.0, 0.5 / 128.0));
+        assert_eq!((data[1].x, data[1].y, data[1].z), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rle_matches_flat() {
+        let w = 8;
+        let px: Vec<[u8; 4]> = (0..w).map(|x| if x < 5 { [10, 20, 30, 130] } else { [x as u8, 1, 2, 131] }).collect();
+        let mut flat = header(w, 1);
+        for p in &px { flat.extend(p); }
+        // RLE por canal: 5 iguales (corrida) + 3 literales
+        let mut rle = header(w, 1);
+        rle.extend([2, 2, 0, w as u8]);
+        for ch in 0..4 {
+            rle.extend([128 + 5, px[0][ch], 3]);
+            rle.extend(px[5..].iter().map(|p| p[ch]));
+        }
+        let a = decode_rgbe(&flat).unwrap().2;
+        let b = decode_rgbe(&rle).unwrap().2;
+        assert!(a.iter().zip(&b).all(|(p, q)| p == q));
+        assert_eq!(a.len(), w);
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        assert!(decode_rgbe(b"P6\n2 2\n").is_none());
+        let mut bytes = header(2, 1);
+        bytes.truncate(bytes.len() - 1);
+        assert!(decode_rgbe(&bytes).is_none());
+    }
+
+    #[test]
+    fn rejects_empty_map() {
+        assert!(decode_rgbe(&header(0, 1)).is_none());
+        assert!(decode_rgbe(&header(2, 0)).is_none());
+        let path = std::env::temp_dir().join(format!("empty_{}.hdr", std::process::id()));
+        fs::write(&path, header(0, 0)).unwrap();
+        let err = EquirectEnv::load(path.to_str().unwrap()).err().expect("mapa vacío aceptado");
+        let _ = fs::remove_file(&path);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,105 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+/// niebla homogénea en toda la escena: absorbe y dispersa con la fase de Henyey-Greenstein
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub density: f32,     // coeficiente de extinción σt (1/unidad)
+    pub albedo: Vector3,  // σs/σt por canal (1 = solo dispersa, no absorbe)
+    pub g: f32,           // anisotropía: > 0 hacia adelante (halo alrededor del sol), 0 isótropa
+    pub steps: u32,       // pasos del ray marching por rayo de cámara
+    pub max_dist: f32,    // alcance de la niebla (el cielo queda detrás)
+}
+
+/// fase de Henyey-Greenstein; `cos` entre la dirección de la luz y la de vista (1 = mirando a la luz)
+pub fn hg_phase(cos: f32, g: f32) -> f32 {
+    let d = 1.0 + g * g - 2.0 * g * cos;
+    (1.0 - g * g) / (4.0 * PI * d * d.sqrt())
+}
+
+impl Fog {
+    pub fn new(density: f32) -> Self {
+        Self { density, albedo: Vector3::new(0.9, 0.9, 0.9), g: 0.6, steps: 16, max_dist: 30.0 }
+    }
+
+    pub fn with_albedo(mut self, albedo: Vector3) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    pub fn with_phase(mut self, g: f32) -> Self {
+        self.g = g.clamp(-0.95, 0.95);
+        self
+    }
+
+    pub fn with_steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// fracción de luz que atraviesa `dist` de niebla
+    pub fn transmittance(&self, dist: f32) -> f32 {
+        (-self.density * dist.min(self.max_dist)).exp()
+    }
+
+    /// dispersión simple a lo largo de ro + rd·s, s ∈ [0, dist] → (transmitancia, luz dispersada hacia el ojo).
+    /// `light(p)` = (dirección hacia la luz, radiancia que llega a p ya con sombra); `ambient` = luz del cielo
+    /// que la niebla dispersa por igual en todas direcciones; `jitter` ∈ [0,1) desplaza los pasos (sin bandas),
+    /// con None no se marcha y solo queda el ambiente (rayos secundarios)
+    pub fn integrate(
+        &self, ro: Vector3, rd: Vector3, dist: f32, jitter: Option<f32>,
+        light: impl Fn(Vector3) -> (Vector3, Vector3), ambient: Vector3,
+    ) -> (f32, Vector3) {
+        let d = dist.min(self.max_dist);
+        let t = (-self.density * d).exp();
+        let sigma_s = self.albedo * self.density;
+        // el ambiente dispersado se integra exacto: ∫ σs·L·e^(-σt s) ds = albedo·L·(1 - T)
+        let mut scattered = Vector3::new(self.albedo.x * ambient.x, self.albedo.y * ambient.y, self.albedo.z * ambient.z) * (1.0 - t);
+        let Some(jitter) = jitter else { return (t, scattered); };
+        if self.steps == 0 || d <= 0.0 { return (t, scattered); }
+
+        let ds = d / self.steps as f32;
+        for i in 0..self.steps {
+            let s = (i as f32 + jitter) * ds;
+            let p = ro + rd * s;
+            let (ldir, radiance) = light(p);
+            if radiance.x + radiance.y + radiance.z <= 0.0 { continue; }
+            let w = hg_phase(rd.dot(ldir), self.g) * (-self.density * s).exp() * ds;
+            scattered += Vector3::new(sigma_s.x * radiance.x, sigma_s.y * radiance.y, sigma_s.z * radiance.z) * w;
+        }
+        (t, scattered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hg_phase_integrates_to_one() {
+        // ∫ p dω = 2π ∫ p(µ) dµ sobre [-1, 1]
+        let n = 20_000;
+        for g in [-0.8, -0.3, 0.0, 0.5, 0.9] {
+            let sum: f32 = (0..n).map(|i| hg_phase(-1.0 + 2.0 * (i as f32 + 0.5) / n as f32, g)).sum();
+            let total = 2.0 * PI * sum * 2.0 / n as f32;
+            assert!((total - 1.0).abs() < 2e-3, "g = {}: {}", g, total);
+        }
+    }
+
+    #[test]
+    fn hg_phase_is_isotropic_at_zero_g() {
+        for cos in [-1.0, -0.4, 0.0, 0.7, 1.0] {
+            assert!((hg_phase(cos, 0.0) - 1.0 / (4.0 * PI)).abs() < 1e-7);
+        }
+    }
+
+    #[test]
+    fn transmittance_is_beer_lambert() {
+        let fog = Fog::new(0.3);
+        for d in [0.0, 0.5, 4.0, 12.0] {
+            assert!((fog.transmittance(d) - (-0.3 * d).exp()).abs() < 1e-6);
+        }
+        // más allá del alcance ya no atenúa más
+        assert_eq!(fog.transmittance(100.0), (-0.3 * fog.max_dist).exp());
+    }
+}
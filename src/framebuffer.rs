@@ -31,6 +31,16 @@ impl Framebuffer {
         let _ = self.color.export_image(path);
     }
 
+    /// PPM ASCII (P3), un pixel por línea
+    pub fn save_ppm(&self, path: &str) -> std::io::Result<()> {
+        use std::fmt::Write as _;
+        let mut out = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for c in self.color.get_image_data().iter() {
+            let _ = writeln!(out, "{} {} {}", c.r, c.g, c.b);
+        }
+        std::fs::write(path, out)
+    }
+
     pub fn blit(&self, rl: &mut RaylibHandle, th: &RaylibThread) {
         if let Ok(tex) = rl.load_texture_from_image(th, &self.color) {
             let mut d = rl.begin_drawing(th);
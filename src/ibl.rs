@@ -0,0 +1,197 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use crate::environment::{Environment, EquirectEnv, latlong_dir};
+use crate::color::luminance;
+
+/// cómo ilumina el entorno las superficies
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IblMode {
+    Off,
+    /// mapas preconvolucionados (irradiancia + lóbulos especulares), sin sombras: barato, sin ruido
+    Prefiltered,
+    /// Monte Carlo: una dirección por impacto según la luminancia del cielo, con rayo de sombra
+    Sampled,
+}
+
+/// exponentes de Phong con lóbulo preconvolucionado (se interpola en log entre ellos)
+const SPEC_EXPONENTS: [f32; 4] = [4.0, 16.0, 64.0, 256.0];
+
+/// el entorno como fuente de luz: copia lat-long de baja resolución con su CDF de luminancia
+/// para muestreo por importancia, más irradiancia y especular preconvolucionados
+pub struct EnvLight {
+    w: usize,
+    h: usize,
+    radiance: Vec<Vector3>,
+    /// CDF marginal por fila (len h+1) y condicional por columna en cada fila (h × (w+1))
+    marginal: Vec<f32>,
+    conditional: Vec<f32>,
+    irradiance: EquirectEnv,
+    specular: Vec<EquirectEnv>,
+}
+
+/// CDF normalizada de `f` (len n+1, empieza en 0) y la suma total
+fn build_cdf(f: &[f32]) -> (Vec<f32>, f32) {
+    let mut cdf = Vec::with_capacity(f.len() + 1);
+    cdf.push(0.0);
+    let mut acc = 0.0;
+    for v in f { acc += v; cdf.push(acc); }
+    let total = acc;
+    if total > 0.0 { for c in cdf.iter_mut() { *c /= total; } }
+    (cdf, total)
+}
+
+/// busca el intervalo de la CDF donde cae u → (índice, posición continua dentro del intervalo)
+fn sample_cdf(cdf: &[f32], u: f32) -> (usize, f32) {
+    let n = cdf.len() - 1;
+    let i = cdf.partition_point(|&c| c <= u).clamp(1, n) - 1;
+    let span = cdf[i + 1] - cdf[i];
+    let t = if span > 0.0 { (u - cdf[i]) / span } else { 0.5 };
+    (i, t)
+}
+
+impl EnvLight {
+    /// remuestrea `env` a w×h (2×2 muestras por texel) y precalcula CDF y convoluciones
+    pub fn new(env: &dyn Environment, w: usize, h: usize) -> Self {
+        let mut radiance = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                let mut acc = Vector3::zero();
+                for (sx, sy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                    acc += env.sample(latlong_dir((x as f32 + sx) / w as f32, (y as f32 + sy) / h as f32));
+                }
+                radiance.push(acc * 0.25);
+            }
+        }
+
+        // densidad ∝ luminancia · sinθ (las filas cerca de los polos cubren menos ángulo sólido);
+        // un piso pequeño evita pdf = 0 donde el cielo es negro
+        let sin_row = |y: usize| ((y as f32 + 0.5) / h as f32 * PI).sin();
+        let mut conditional = Vec::with_capacity(h * (w + 1));
+        let mut row_weight = Vec::with_capacity(h);
+        for y in 0..h {
+            let f: Vec<f32> = radiance[y * w..(y + 1) * w].iter()
+                .map(|c| (luminance(*c) + 1e-3) * sin_row(y)).collect();
+            let (cdf, total) = build_cdf(&f);
+            conditional.extend(cdf);
+            row_weight.push(total);
+        }
+        let (marginal, _) = build_cdf(&row_weight);
+
+        // convoluciones en una rejilla más chica: cada texel de salida integra toda la rejilla de entrada
+        let (cw, ch) = ((w / 2).max(4), (h / 2).max(2));
+        // (dirección, radiancia · ángulo sólido) de cada texel de entrada
+        let texels: Vec<(Vector3, Vector3)> = (0..w * h).map(|i| {
+            let (x, y) = (i % w, i / w);
+            let d_omega = (2.0 * PI / w as f32) * (PI / h as f32) * sin_row(y);
+            (latlong_dir((x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32), radiance[i] * d_omega)
+        }).collect();
+        let convolve = |lobe: &dyn Fn(Vector3, Vector3) -> f32| {
+            let out = (0..cw * ch).map(|i| {
+                let n = latlong_dir(((i % cw) as f32 + 0.5) / cw as f32, ((i / cw) as f32 + 0.5) / ch as f32);
+                texels.iter().fold(Vector3::zero(), |acc, &(l, le)| {
+                    let k = lobe(n, l);
+                    if k > 0.0 { acc + le * k } else { acc }
+                })
+            }).collect();
+            EquirectEnv::from_data(cw, ch, out)
+        };
+        // irradiancia normalizada: un cielo blanco uniforme da 1
+        let irradiance = convolve(&|n, l| n.dot(l).max(0.0) / PI);
+        // lóbulo de Phong normalizado: (s+1)/2π · cos^s
+        let specular = SPEC_EXPONENTS.iter()
+            .map(|&s| convolve(&|r, l| (s + 1.0) / (2.0 * PI) * r.dot(l).max(0.0).powf(s)))
+            .collect();
+
+        Self { w, h, radiance, marginal, conditional, irradiance, specular }
+    }
+
+    /// irradiancia difusa (sin sombras) para la normal n, normalizada a albedo
+    pub fn irradiance(&self, n: Vector3) -> Vector3 { self.irradiance.sample(n) }
+
+    /// reflejo brillante preconvolucionado alrededor de la dirección reflejada r
+    pub fn specular(&self, r: Vector3, exponent: f32) -> Vector3 {
+        let e = exponent.clamp(SPEC_EXPONENTS[0], SPEC_EXPONENTS[SPEC_EXPONENTS.len() - 1]);
+        let i = SPEC_EXPONENTS.iter().rposition(|&s| s <= e).unwrap_or(0).min(SPEC_EXPONENTS.len() - 2);
+        let t = (e / SPEC_EXPONENTS[i]).ln() / (SPEC_EXPONENTS[i + 1] / SPEC_EXPONENTS[i]).ln();
+        self.specular[i].sample(r).lerp(self.specular[i + 1].sample(r), t.clamp(0.0, 1.0))
+    }
+
+    /// dirección según la luminancia del cielo → (dirección, radiancia, pdf en ángulo sólido)
+    pub fn sample(&self, u1: f32, u2: f32) -> (Vector3, Vector3, f32) {
+        let (y, ty) = sample_cdf(&self.marginal, u1);
+        let row = &self.conditional[y * (self.w + 1)..(y + 1) * (self.w + 1)];
+        let (x, tx) = sample_cdf(row, u2);
+        let (u, v) = ((x as f32 + tx) / self.w as f32, (y as f32 + ty) / self.h as f32);
+        let dir = latlong_dir(u, v);
+        // pdf en (u,v) = probabilidad del texel · w·h; pasa a ángulo sólido con 2π²·sinθ
+        let p_row = self.marginal[y + 1] - self.marginal[y];
+        let p_col = row[x + 1] - row[x];
+        let pdf_uv = p_row * p_col * (self.w * self.h) as f32;
+        let sin_t = (v * PI).sin().max(1e-4);
+        (dir, self.radiance[y * self.w + x], pdf_uv / (2.0 * PI * PI * sin_t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// cielo blanco uniforme
+    struct White;
+    impl Environment for White {
+        fn sample(&self, _: Vector3) -> Vector3 { Vector3::one() }
+    }
+
+    /// cielo con un sol chico muy brillante: la CDF tiene que concentrarse ahí
+    struct SunSky;
+    impl Environment for SunSky {
+        fn sample(&self, d: Vector3) -> Vector3 {
+            let sun = Vector3::new(0.3, 0.8, -0.5).normalized();
+            if d.dot(sun) > 0.97 { Vector3::one() * 50.0 } else { Vector3::one() * (0.2 + 0.3 * d.y.max(0.0)) }
+        }
+    }
+
+    /// muestras estratificadas n×n en [0,1)²
+    fn grid(n: usize) -> impl Iterator<Item = (f32, f32)> {
+        (0..n * n).map(move |i| (((i % n) as f32 + 0.5) / n as f32, ((i / n) as f32 + 0.5) / n as f32))
+    }
+
+    #[test]
+    fn cdf_is_normalized_and_inverts() {
+        let (cdf, total) = build_cdf(&[1.0, 0.0, 3.0]);
+        assert_eq!(total, 4.0);
+        assert_eq!(cdf, vec![0.0, 0.25, 0.25, 1.0]);
+        assert_eq!(sample_cdf(&cdf, 0.1), (0, 0.4));
+        // el intervalo vacío nunca se elige
+        assert_eq!(sample_cdf(&cdf, 0.25).0, 2);
+        assert_eq!(sample_cdf(&cdf, 0.625), (2, 0.5));
+    }
+
+    #[test]
+    fn sampled_pdf_integrates_to_one() {
+        // E[1/pdf] = ∫ dω = 4π si la pdf está normalizada en la esfera
+        for env in [&White as &dyn Environment, &SunSky] {
+            let light = EnvLight::new(env, 64, 32);
+            let n = 256;
+            let sum: f32 = grid(n).map(|(u1, u2)| 1.0 / light.sample(u1, u2).2).sum();
+            let area = sum / (n * n) as f32;
+            assert!((area / (4.0 * PI) - 1.0).abs() < 0.02, "∫dω = {}", area);
+        }
+    }
+
+    #[test]
+    fn white_sky_gives_unit_irradiance() {
+        let light = EnvLight::new(&White, 64, 32);
+        for n in [Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.3, -0.5, 0.8).normalized()] {
+            let e = light.irradiance(n);
+            assert!((e.x - 1.0).abs() < 0.02 && (e.y - 1.0).abs() < 0.02 && (e.z - 1.0).abs() < 0.02, "{:?}", e);
+            // el estimador muestreado también: L·cosθ / (π·pdf)
+            let k = 256;
+            let mc: f32 = grid(k).map(|(u1, u2)| {
+                let (d, l, pdf) = light.sample(u1, u2);
+                l.x * d.dot(n).max(0.0) / (PI * pdf)
+            }).sum::<f32>() / (k * k) as f32;
+            assert!((mc - 1.0).abs() < 0.02, "muestreado {}", mc);
+        }
+    }
+}
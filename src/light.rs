@@ -1,3 +1,22 @@
 use raylib::prelude::*;
-pub struct Light { pub pos: Vector3, pub color: Vector3, pub intensity: f32 }
-impl Light { pub fn new(pos: Vector3, color: Vector3, intensity: f32) -> Self { Self{pos,color,intensity} } }
+pub struct Light {
+    pub pos: Vector3, pub color: Vector3, pub intensity: f32,
+    /// Some = luz direccional (sol): dirección hacia la luz, `pos` no se usa
+    pub direction: Option<Vector3>,
+    /// Some = color de cuerpo negro a esa temperatura (K): el modo espectral usa el espectro de Planck
+    pub temperature: Option<f32>,
+}
+impl Light {
+    pub fn new(pos: Vector3, color: Vector3, intensity: f32) -> Self { Self{pos,color,intensity,direction:None,temperature:None} }
+    pub fn sun(direction: Vector3, color: Vector3, intensity: f32) -> Self {
+        Self { pos: Vector3::zero(), color, intensity, direction: Some(direction.normalized()), temperature: None }
+    }
+    pub fn with_temperature(mut self, kelvin: f32) -> Self { self.temperature = Some(kelvin); self }
+    /// dirección hacia la luz desde p y distancia (infinita si es direccional)
+    pub fn toward(&self, p: Vector3) -> (Vector3, f32) {
+        match self.direction {
+            Some(d) => (d, f32::INFINITY),
+            None => { let v = self.pos - p; let dist = v.length(); (v / dist, dist) }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use raylib::prelude::*;
+use crate::color::{blackbody, luminance};
+use crate::environment::{Environment, EquirectEnv};
+use crate::ibl::EnvLight;
+use crate::light::Light;
+use crate::skybox::Skybox;
+use crate::sky::PhysicalSky;
+
+/// parámetros del cielo físico que no cambian con la hora
+#[derive(Clone, Copy, Debug)]
+pub struct SkyParams {
+    pub latitude: f32,  // grados
+    pub day: f32,       // día del año
+    pub turbidity: f32,
+    pub exposure: f32,
+    pub stars: f32,     // brillo del campo de estrellas de noche
+}
+
+/// iluminación de la escena según la hora: cielo (cubemap, panorama o Preetham), luz directa
+/// (lámpara, sol o luna) y el entorno como luz; de noche se encienden las ventanas
+pub struct Lighting<'a> {
+    cubemap: &'a Skybox,
+    panorama: Option<&'a EquirectEnv>,
+    params: SkyParams,
+    lamp: Light,
+    sun_intensity: f32,
+    pub physical: Option<PhysicalSky>,
+    pub light: Light,
+    pub env_light: EnvLight,
+    pub hour: f32,       // hora solar (0-24)
+    ibl_hour: f32,       // hora con la que se calculó `env_light`
+    pub night: f32,      // 0 de día, 1 de noche cerrada
+    pub windows: bool,   // ventanas encendidas al oscurecer
+}
+
+/// temperatura de color del sol: ~2000 K en el horizonte, ~5800 K desde unos 30° de altura
+fn sun_temperature(elevation_sin: f32) -> f32 {
+    let e = (elevation_sin / 0.5).clamp(0.0, 1.0);
+    2000.0 + 3800.0 * e * (2.0 - e)
+}
+
+fn new_sky(p: &SkyParams, hour: f32) -> PhysicalSky {
+    let mut sky = PhysicalSky::new(PhysicalSky::sun_direction(hour, p.latitude, p.day), p.turbidity)
+        .with_exposure(p.exposure);
+    sky.stars = p.stars;
+    sky
+}
+
+/// cielo activo: Preetham si está encendido, si no el panorama del archivo o el cubemap
+fn active_sky<'s>(physical: &'s Option<PhysicalSky>, panorama: Option<&'s EquirectEnv>, cubemap: &'s Skybox) -> &'s dyn Environment {
+    match (physical, panorama) {
+        (Some(sky), _) => sky,
+        (None, Some(env)) => env,
+        (None, None) => cubemap,
+    }
+}
+
+impl<'a> Lighting<'a> {
+    pub fn new(
+        cubemap: &'a Skybox, panorama: Option<&'a EquirectEnv>, params: SkyParams,
+        lamp: Light, sun_intensity: f32, hour: f32, physical: bool,
+    ) -> Self {
+        let physical = physical.then(|| new_sky(&params, hour));
+        let env_light = EnvLight::new(active_sky(&physical, panorama, cubemap), 64, 32);
+        let light = Light::new(lamp.pos, lamp.color, lamp.intensity);
+        let mut l = Self {
+            cubemap, panorama, params, lamp, sun_intensity, physical, light,
+            env_light, hour, ibl_hour: hour, night: 0.0, windows: true,
+        };
+        l.set_hour(hour);
+        l
+    }
+
+    pub fn sky(&self) -> &dyn Environment { active_sky(&self.physical, self.panorama, self.cubemap) }
+
+    /// enciende/apaga el cielo físico (recalcula luz e IBL)
+    pub fn toggle_physical(&mut self) {
+        self.physical = if self.physical.is_some() { None } else { Some(new_sky(&self.params, self.hour)) };
+        self.set_hour(self.hour);
+        self.rebuild_ibl();
+    }
+
+    /// mueve el sol a la hora `hour`; el IBL queda como estaba (ver `rebuild_ibl`)
+    pub fn set_hour(&mut self, hour: f32) {
+        self.hour = hour.rem_euclid(24.0);
+        let Some(sky) = &mut self.physical else {
+            self.light = Light::new(self.lamp.pos, self.lamp.color, self.lamp.intensity);
+            self.night = 0.0;
+            return;
+        };
+        let p = &self.params;
+        sky.set_sun(PhysicalSky::sun_direction(self.hour, p.latitude, p.day));
+        self.night = 1.0 - sky.sun_fade();
+
+        let sun = sky.sun_dir;
+        if self.night < 0.5 {
+            // sol: color de cuerpo negro según la altura; la intensidad sigue a la transmitancia
+            // de la atmósfera (respecto del sol alto) y se apaga en el crepúsculo
+            let strength = (luminance(sky.sun_color()) / 0.75).min(1.0);
+            let kelvin = sun_temperature(sun.y);
+            self.light = Light::sun(sun, blackbody(kelvin), self.sun_intensity * strength).with_temperature(kelvin);
+        } else {
+            // luna: opuesta al sol, siempre algo alta para que haga sombras; azulada y tenue
+            let moon = Vector3::new(-sun.x, sun.y.abs().max(0.3), -sun.z);
+            let k = ((self.night - 0.5) * 2.0).clamp(0.0, 1.0);
+            self.light = Light::sun(moon, blackbody(8000.0), 0.25 * k).with_temperature(8000.0);
+        }
+    }
+
+    /// recalcula el entorno como luz para el cielo actual
+    pub fn rebuild_ibl(&mut self) {
+        self.env_light = EnvLight::new(self.sky(), 64, 32);
+        self.ibl_hour = self.hour;
+    }
+
+    /// avanza el reloj `hours`; el IBL se recalcula cada cuarto de hora simulado
+    pub fn advance(&mut self, hours: f32) {
+        self.set_hour(self.hour + hours);
+        let d = (self.hour - self.ibl_hour).rem_euclid(24.0);
+        if d.min(24.0 - d) >= 0.25 { self.rebuild_ibl(); }
+    }
+
+    /// cuánto brillan los materiales emisivos (ventanas) ahora
+    pub fn emission(&self) -> f32 {
+        if self.windows { self.night } else { 0.0 }
+    }
+}
@@ -1,5 +1,5 @@
 use raylib::prelude::*;
-use std::f32::consts::PI;
+use rayon::prelude::*;
 
 mod camera;
 mod framebuffer;
@@ -9,19 +9,55 @@ mod cube;
 mod light;
 mod texture;
 mod skybox;
+mod procedural;
+mod scene_file;
+mod rng;
+mod animation;
+mod motion;
+mod environment;
+mod ibl;
+mod sky;
+mod color;
+mod lighting;
+mod fog;
+mod volume;
+mod water;
+mod photon;
+mod ao;
+mod dispersion;
+mod prism;
+mod spectral;
 
-use camera::Camera;
+use camera::{Camera, CameraMode, Projection, Stereo, Eye};
 use framebuffer::Framebuffer;
 use material::{Material, v3_to_color};
-use ray_intersect::{Intersect, RayIntersect, reflect, refract, offset_origin};
+use ray_intersect::{Intersect, RayIntersect, RayDiff, reflect, refract, offset_origin, ORIGIN_BIAS};
 use cube::Cube;
 use light::Light;
-use texture::TextureCPU;
+use texture::{Texture, TextureCPU, Filter, Wrap, UvTransform};
 use skybox::Skybox;
+use procedural::{Procedural, Pattern, Space};
+use scene_file::SceneFile;
+use rng::Rng;
+use animation::{CameraPath, Recorder};
+use motion::{Moving, Transform};
+use environment::{Environment, EquirectEnv};
+use ibl::{EnvLight, IblMode};
+use color::{BlackbodySpectrum, blackbody, wavelength_to_rgb};
+use lighting::{Lighting, SkyParams};
+use fog::{Fog, hg_phase};
+use volume::{DensityGrid, Volume};
+use water::{Water, Wave};
+use photon::{PhotonMap, shoot_caustics};
+use ao::{AmbientOcclusion, AoMode, cosine_hemisphere};
+use dispersion::{Dispersion, RGB_WAVELENGTHS, channel_mask};
+use prism::Prism;
+
+const SCENE_PATH: &str = "assets/scene.txt";
 
 // === sombreado ===
 fn phong_shade(hit: &Intersect, light: &Light, view_dir: Vector3) -> (Vector3 /*kd*/, f32 /*spec*/) {
-    let ldir = (light.pos - hit.point).normalized();
+    let (ldir, _) = light.toward(hit.point);
     let ndotl = hit.normal.dot(ldir).max(0.0);
 
     // componemos kd por componente (evita Vector3 * Vector3 directo)
@@ -34,99 +70,510 @@ fn phong_shade(hit: &Intersect, light: &Light, view_dir: Vector3) -> (Vector3 /*
     (kd, spec)
 }
 
+/// todo lo que un rayo necesita consultar de la escena
+struct Scene<'a> {
+    objects: &'a [Box<dyn RayIntersect + Sync>],
+    light: &'a Light,
+    sky: &'a dyn Environment,
+    env_light: &'a EnvLight,
+    ibl: IblMode,
+    ibl_strength: f32,
+    emission: f32, // brillo de los materiales emisivos (ventanas de noche)
+    fog: Option<&'a Fog>,
+    volumes: &'a [Volume],
+    caustics: Option<&'a PhotonMap>,
+    ao: AmbientOcclusion,
+    tex_albedo: &'a (dyn Fn(&Intersect)->Vector3 + Sync),
+    shadows: bool,           // rayos de sombra para la luz directa de las superficies
+    spectral: bool,          // cada muestra en una longitud de onda, convertida a RGB al final
+    wavelength: Option<f32>, // la del camino en curso (nm)
+}
+
+impl Scene<'_> {
+    /// un albedo o tinte en la longitud de onda del camino (tal cual en RGB)
+    fn reflectance(&self, c: Vector3) -> Vector3 { self.wavelength.map_or(c, |l| spectral::reflectance(c, l)) }
+
+    /// una radiancia (entorno, emisión) en la longitud de onda del camino
+    fn radiance(&self, c: Vector3) -> Vector3 { self.wavelength.map_or(c, |l| spectral::radiance(c, l)) }
+
+    /// impacto más cercano en el instante `time`, atravesando texels recortados por alfa
+    fn trace(&self, ro: &Vector3, rd: &Vector3, time: f32) -> Intersect {
+        let mut o = *ro;
+        let mut travelled = 0.0;
+        // tope de capas recortadas (cada caja recortada aporta entrada y salida)
+        for _ in 0..16 {
+            let mut best = Intersect::empty();
+            let mut z = f32::INFINITY;
+            for obj in self.objects {
+                let i = obj.ray_intersect_at(&o, rd, time);
+                if i.hit && i.t < z { z = i.t; best = i; }
+            }
+            if !best.hit || !best.mat.is_cut_out(&best) {
+                best.t += travelled;
+                return best;
+            }
+            // seguimos justo detrás del texel transparente
+            o = best.point + *rd * ORIGIN_BIAS;
+            travelled += best.t + ORIGIN_BIAS;
+        }
+        Intersect::empty()
+    }
+
+    /// luz que llega desde la luz hasta p: 0 si algo opaco la tapa, atenuada por superficies
+    /// transparentes (kt) y sin pérdida a través de recortes por alfa
+    fn transmittance(&self, p: &Vector3, n: &Vector3, time: f32) -> Vector3 {
+        let (ldir, dist) = self.light.toward(*p);
+        self.visibility(p, n, &ldir, dist, time)
+    }
+
+    /// lo mismo hacia una dirección, hasta `dist` (infinito para el cielo)
+    fn visibility(&self, p: &Vector3, n: &Vector3, ldir: &Vector3, dist: f32, time: f32) -> Vector3 {
+        let ldir = *ldir;
+        let mut o = offset_origin(p, n, &ldir);
+        let mut left = dist;
+        // los volúmenes (humo) también hacen sombra
+        let mut vis = Vector3::one() * self.volumes.iter().map(|v| v.transmittance(p, &ldir, dist)).product::<f32>();
+        for _ in 0..16 {
+            let hit = self.trace(&o, &ldir, time);
+            if !hit.hit || hit.t >= left { return vis; }
+            let kt = hit.mat.albedo[3];
+            // con cáusticas, la luz que cruza el agua y el vidrio que apuntan los fotones llega por el
+            // mapa (ya enfocada); lo demás transparente sigue dejando pasar la luz directa
+            if kt <= 0.0 || (hit.mat.ior != 1.0 && self.caustics.is_some_and(|m| m.covers(hit.point))) {
+                return Vector3::zero();
+            }
+            // sombra coloreada por el tinte del material transparente
+            vis = vis * self.reflectance(hit.mat.diffuse) * kt;
+            o = hit.point + ldir * ORIGIN_BIAS;
+            left -= hit.t + ORIGIN_BIAS;
+        }
+        vis
+    }
+
+    /// (dirección hacia la luz, radiancia que llega de ella a p con sombras): para los medios
+    fn light_at(&self, p: Vector3, time: f32) -> (Vector3, Vector3) {
+        let (ldir, ldist) = self.light.toward(p);
+        let vis = self.visibility(&p, &ldir, &ldir, ldist, time);
+        let l = self.light.color * self.light.intensity;
+        (ldir, Vector3::new(l.x * vis.x, l.y * vis.y, l.z * vis.z))
+    }
+
+    /// fracción del hemisferio de n (pesada por coseno) sin obstáculos a menos de `ao.radius`
+    fn ambient_occlusion(&self, p: &Vector3, n: &Vector3, time: f32, rng: &mut Rng) -> f32 {
+        let open = (0..self.ao.samples).filter(|_| {
+            let (u1, u2) = rng.next_2d();
+            let d = cosine_hemisphere(*n, u1, u2);
+            let hit = self.trace(&offset_origin(p, n, &d), &d, time);
+            !hit.hit || hit.t > self.ao.radius
+        }).count();
+        open as f32 / self.ao.samples as f32
+    }
+
+    /// luz del cielo que llega a un medio, tomada como igual en todas direcciones
+    fn sky_ambient(&self) -> Vector3 {
+        if self.ibl == IblMode::Off { return Vector3::zero(); }
+        self.radiance(self.env_light.irradiance(Vector3::new(0.0, 1.0, 0.0))) * self.ibl_strength
+    }
+}
+
 fn cast_ray(
     ro: &Vector3, rd: &Vector3,
-    objects: &[Box<dyn RayIntersect + Sync>],
-    light: &Light,
-    sky: &Skybox,
-    tex_albedo: &dyn Fn(&Intersect)->Vector3,
+    diff: &RayDiff,
+    scene: &Scene,
+    time: f32,
+    rng: &mut Rng,
     depth: u32
 ) -> Vector3 {
-    if depth > 3 { return sky.sample(*rd); }
+    if depth > 3 { return scene.sky.sample(*rd); }
 
-    let mut best = Intersect::empty();
-    let mut z = f32::INFINITY;
-    for o in objects {
-        let i = o.ray_intersect(ro, rd);
-        if i.hit && i.t < z { z = i.t; best = i; }
+    let best = scene.trace(ro, rd, time);
+    if scene.ao.mode == AoMode::Only {
+        // modo arcilla: blanco con el hemisferio libre, cielo blanco
+        if !best.hit { return Vector3::one(); }
+        let n = if best.normal.dot(*rd) > 0.0 { -best.normal } else { best.normal };
+        return Vector3::one() * scene.ambient_occlusion(&best.point, &n, time, rng);
     }
-    if !best.hit { return sky.sample(*rd); }
+    let mut dist = if best.hit { best.t } else { f32::INFINITY };
+
+    // volúmenes: delta tracking hasta la superficie; en la colisión más cercana se dispersa
+    // la luz directa (con sombra) y el cielo, y lo que hay detrás queda tapado
+    let mut collision = None;
+    for vol in scene.volumes {
+        if let Some(t) = vol.sample_collision(ro, rd, dist, rng) {
+            dist = t;
+            collision = Some(vol);
+        }
+    }
+    let color = if let Some(vol) = collision {
+        let p = *ro + *rd * dist;
+        let (ldir, l) = scene.light_at(p, time);
+        let s = l * hg_phase(rd.dot(ldir), vol.g) + scene.sky_ambient();
+        let a = scene.reflectance(vol.albedo);
+        Vector3::new(a.x * s.x, a.y * s.y, a.z * s.z)
+    } else if best.hit {
+        shade(rd, diff, best, scene, time, rng, depth)
+    } else {
+        scene.sky.sample(*rd)
+    };
+    let Some(fog) = scene.fog else { return color; };
+
+    // niebla entre el origen y el impacto: atenúa lo que hay detrás y suma la luz que dispersa;
+    // los haces de luz (sombras de la luz dentro de la niebla) solo en rayos de cámara
+    let jitter = (depth == 0).then(|| rng.next_f32());
+    let (t, scattered) = fog.integrate(*ro, *rd, dist, jitter, |p| {
+        let (ldir, l) = scene.light_at(p, time);
+        (ldir, l * fog.transmittance(scene.light.toward(p).1))
+    }, scene.sky_ambient());
+    color * t + scattered
+}
+
+/// luz que sale de la superficie `best` hacia el origen del rayo
+fn shade(
+    rd: &Vector3,
+    diff: &RayDiff,
+    mut best: Intersect,
+    scene: &Scene,
+    time: f32,
+    rng: &mut Rng,
+    depth: u32
+) -> Vector3 {
+    let Scene { light, tex_albedo, .. } = *scene;
+
+    // huella del pixel en uv para elegir mip
+    best.set_footprint(rd, diff);
 
     // texturas (albedo multiplicativo)
-    let base_tex = tex_albedo(&best);
-    let view_dir = (*ro - best.point).normalized();
+    let base_tex = scene.reflectance(tex_albedo(&best));
+    best.mat.diffuse = scene.reflectance(best.mat.diffuse);
+
+    // normal de sombreado (normal/bump map); la geométrica se queda para desplazar orígenes
+    let n_geo = best.normal;
+    best.normal = best.mat.shading_normal(&best);
+    let view_dir = -rd.normalized();
     let (kd_col, spec_sc) = phong_shade(&best, light, view_dir);
     let kd = Vector3::new(
         kd_col.x * base_tex.x,
         kd_col.y * base_tex.y,
         kd_col.z * base_tex.z
     );
-    let ks = Vector3::new(spec_sc, spec_sc, spec_sc) * light.intensity;
+    let ks = light.color * (spec_sc * light.intensity);
+
+    // sombra (rayo hacia la luz; los recortes por alfa dejan pasar la luz); las cáusticas la necesitan
+    // porque la luz que cruza agua y vidrio llega por el mapa de fotones
+    let vis = if scene.shadows || scene.caustics.is_some() {
+        scene.transmittance(&best.point, &n_geo, time)
+    } else {
+        Vector3::one()
+    };
+    let (kd, ks) = (kd * vis, ks * vis);
 
     // componentes
     let (ka, ks_w, kr, kt) = (best.mat.albedo[0], best.mat.albedo[1], best.mat.albedo[2], best.mat.albedo[3]);
 
-    let mut color = kd * ka + ks * ks_w;
+    // oclusión ambiental en los impactos primarios: oscurece el término ambiente y el IBL
+    let occlusion = if depth == 0 && scene.ao.mode == AoMode::Shading {
+        scene.ambient_occlusion(&best.point, &n_geo, time, rng)
+    } else { 1.0 };
+
+    let mut color = kd * (ka * occlusion) + ks * ks_w;
+
+    // luz del entorno (IBL): difusa con el albedo texturizado, brillo especular según shininess
+    let env = scene.env_light;
+    let diffuse_env = match scene.ibl {
+        IblMode::Off => Vector3::zero(),
+        IblMode::Prefiltered => scene.radiance(env.irradiance(best.normal)),
+        IblMode::Sampled => {
+            // una muestra por impacto: L·cosθ / (π·pdf), con sombra
+            let (u1, u2) = rng.next_2d();
+            let (dir, radiance, pdf) = env.sample(u1, u2);
+            let cos = dir.dot(best.normal);
+            if cos > 0.0 && pdf > 0.0 {
+                let vis = scene.visibility(&best.point, &n_geo, &dir, f32::INFINITY, time);
+                let l = scene.radiance(radiance) * (cos / (std::f32::consts::PI * pdf));
+                Vector3::new(l.x * vis.x, l.y * vis.y, l.z * vis.z)
+            } else {
+                Vector3::zero()
+            }
+        }
+    };
+    if scene.ibl != IblMode::Off {
+        let d = Vector3::new(diffuse_env.x * base_tex.x, diffuse_env.y * base_tex.y, diffuse_env.z * base_tex.z);
+        // los espejos ya ven el cielo por la recursión de reflexión
+        let spec_env = scene.radiance(env.specular(reflect(rd, &best.normal).normalized(), best.mat.specular_exp));
+        color += (d * ka + spec_env * (ks_w * (1.0 - kr))) * (scene.ibl_strength * occlusion);
+    }
+
+    // cáusticas: luz enfocada por agua y vidrio, estimada con los fotones cercanos
+    if let Some(map) = scene.caustics && ka > 0.0 {
+        let e = scene.radiance(map.irradiance(best.point, n_geo, 64, 0.15));
+        let a = best.mat.diffuse * ka;
+        color += Vector3::new(e.x * a.x * base_tex.x, e.y * a.y * base_tex.y, e.z * a.z * base_tex.z);
+    }
 
     // reflexión
     if kr > 0.0 {
         let rdir = reflect(rd, &best.normal).normalized();
-        let rorig = offset_origin(&best.point, &best.normal, &rdir);
-        let rc = cast_ray(&rorig, &rdir, objects, light, sky, tex_albedo, depth+1);
+        let rorig = offset_origin(&best.point, &n_geo, &rdir);
+        let rdiff = diff.reflected(rd, best.t, &n_geo);
+        let rc = cast_ray(&rorig, &rdir, &rdiff, scene, time, rng, depth+1);
         color = color*(1.0-kr) + rc*kr;
     }
 
-    // refracción
+    // refracción; con dispersión cada canal sigue su propio rayo (índice de su longitud de onda),
+    // en modo espectral un solo rayo con el índice exacto del camino
     if kt > 0.0 {
-        if let Some(tdir) = refract(rd, &best.normal, best.mat.ior) {
-            let torig = offset_origin(&best.point, &best.normal, &tdir);
-            let tc = cast_ray(&torig, &tdir, objects, light, sky, tex_albedo, depth+1);
-            color = color*(1.0-kt) + tc*kt;
-        } else {
-            // TIR: ya lo maneja la reflexión de arriba
+        let passes = if best.mat.dispersion.is_some() && scene.wavelength.is_none() { 3 } else { 1 };
+        // TIR: el canal queda como está (ya lo maneja la reflexión de arriba)
+        let mut tc = color;
+        for (c, &lambda) in RGB_WAVELENGTHS.iter().enumerate().take(passes) {
+            let (ior, mask) = match scene.wavelength {
+                Some(l) => (best.mat.ior_at(l), Vector3::one()),
+                None if passes == 3 => (best.mat.ior_at(lambda), channel_mask(c)),
+                None => (best.mat.ior, Vector3::one()),
+            };
+            let Some(tdir) = refract(rd, &best.normal, ior) else { continue; };
+            let torig = offset_origin(&best.point, &n_geo, &tdir);
+            // eta del lado por el que entra el rayo
+            let eta = if rd.dot(n_geo) < 0.0 { 1.0 / ior } else { ior };
+            let tdiff = diff.refracted(rd, best.t, &n_geo, eta);
+            let c = cast_ray(&torig, &tdir, &tdiff, scene, time, rng, depth+1);
+            tc = Vector3::new(
+                tc.x + (c.x - tc.x) * mask.x,
+                tc.y + (c.y - tc.y) * mask.y,
+                tc.z + (c.z - tc.z) * mask.z,
+            );
         }
+        color = color*(1.0-kt) + tc*kt;
     }
 
+    // luz propia: se suma sin que la tapen reflejo ni transparencia
+    if scene.emission > 0.0 { color += scene.radiance(best.mat.emission) * scene.emission; }
+
     color
 }
 
-fn render(
-    fb: &mut Framebuffer,
-    cam: &Camera,
-    light: &Light,
-    sky: &Skybox,
-    objects: &[Box<dyn RayIntersect + Sync>],
-    tex_albedo: &dyn Fn(&Intersect)->Vector3
-) {
-    let w = fb.width as f32;
-    let h = fb.height as f32;
-    let aspect = w/h;
-    let fov = PI/3.0;
-    let scale = (fov*0.5).tan();
-
-    for y in 0..fb.height {
-        for x in 0..fb.width {
-            let sx = (2.0 * x as f32) / w - 1.0;
-            let sy = -(2.0 * y as f32) / h + 1.0;
-            let sx = sx * aspect * scale;
-            let sy = sy * scale;
-
-            let rd_cam = Vector3::new(sx, sy, -1.0).normalized();
-            let rd = cam.basis_change(&rd_cam).normalized();
-            let col = cast_ray(&cam.eye, &rd, objects, light, sky, tex_albedo, 0);
-
-            fb.set_current_color(v3_to_color(col));
-            fb.set_pixel(x, y);
+/// `spp` muestras por pixel (antialias + apertura de la lente); `frame` varía la semilla
+/// imagen wi×hi en color lineal, por filas (sin tocar el framebuffer)
+fn trace_image(cam: &Camera, scene: &Scene, wi: u32, hi: u32, spp: u32, frame: u32) -> Vec<Vec<Vector3>> {
+    let w = wi as f32;
+    let h = hi as f32;
+    let spp = spp.max(1);
+    // modo espectral (no en el modo arcilla, que ya es gris): el espectro de la luz se arma una vez
+    let spectral = scene.spectral && scene.ao.mode != AoMode::Only;
+    let planck = scene.light.temperature.map(BlackbodySpectrum::new);
+
+    // filas en paralelo
+    (0..hi).into_par_iter().map(|y| {
+        (0..wi).map(|x| {
+            let mut rng = Rng::for_pixel(x, y, frame);
+            let mut acc = Vector3::zero();
+            for i in 0..spp {
+                // con una sola muestra se conserva la esquina del pixel (imagen de siempre)
+                let (jx, jy) = if spp == 1 { (0.0, 0.0) } else { rng.next_2d() };
+                // instante dentro de la exposición (desenfoque de movimiento)
+                let u = if cam.shutter > 0.0 { rng.next_f32() } else { 0.0 };
+                let moved;
+                let c = if cam.motion_end.is_some() { moved = cam.at_shutter(u); &moved } else { cam };
+                // fuera de la proyección (esquinas del ojo de pez) queda negro
+                let Some((ro, rd, diff)) = c.primary_ray(x as f32 + jx, y as f32 + jy, w, h, rng.next_2d()) else { continue; };
+                if !spectral {
+                    acc += cast_ray(&ro, &rd, &diff, scene, cam.ray_time(u), &mut rng, 0);
+                    continue;
+                }
+                // una longitud de onda por camino; el resultado es gris (su valor en λ) y las CIE lo pasan a RGB
+                let lambda = spectral::wavelength(i, spp, rng.next_f32());
+                let light = spectral::light_at(scene.light, planck.as_ref(), lambda);
+                let sky = spectral::EnvAt { inner: scene.sky, lambda };
+                let fog = scene.fog.map(|f| spectral::fog_at(f, lambda));
+                let s = Scene { light: &light, sky: &sky, fog: fog.as_ref(), wavelength: Some(lambda), ..*scene };
+                let v = cast_ray(&ro, &rd, &diff, &s, cam.ray_time(u), &mut rng, 0);
+                acc += wavelength_to_rgb(lambda) * ((v.x + v.y + v.z) / 3.0);
+            }
+            acc / spp as f32
+        }).collect()
+    }).collect()
+}
+
+fn render(fb: &mut Framebuffer, cam: &Camera, scene: &Scene, spp: u32, frame: u32) {
+    let (w, h) = (fb.width, fb.height);
+    let rows = match cam.stereo {
+        Stereo::Off => trace_image(cam, scene, w, h, spp, frame),
+        Stereo::SideBySide => {
+            let left = trace_image(&cam.eye_view(Eye::Left), scene, w / 2, h, spp, frame);
+            let right = trace_image(&cam.eye_view(Eye::Right), scene, w - w / 2, h, spp, frame);
+            left.into_iter().zip(right).map(|(mut l, r)| { l.extend(r); l }).collect()
+        }
+        Stereo::TopBottom => {
+            let mut top = trace_image(&cam.eye_view(Eye::Left), scene, w, h / 2, spp, frame);
+            top.extend(trace_image(&cam.eye_view(Eye::Right), scene, w, h - h / 2, spp, frame));
+            top
+        }
+        Stereo::Anaglyph => {
+            // medio color: el rojo en gris reduce la rivalidad entre ojos en objetos rojos/cian
+            let left = trace_image(&cam.eye_view(Eye::Left), scene, w, h, spp, frame);
+            let right = trace_image(&cam.eye_view(Eye::Right), scene, w, h, spp, frame);
+            left.into_iter().zip(right).map(|(l, r)| {
+                l.into_iter().zip(r).map(|(a, b)| {
+                    Vector3::new(0.299 * a.x + 0.587 * a.y + 0.114 * a.z, b.y, b.z)
+                }).collect()
+            }).collect()
+        }
+    };
+
+    // el framebuffer se escribe en serie
+    for (y, row) in rows.iter().enumerate() {
+        for (x, col) in row.iter().enumerate() {
+            fb.set_current_color(v3_to_color(*col));
+            fb.set_pixel(x as u32, y as u32);
+        }
+    }
+}
+
+/// lo fijo de la escena (geometría, texturas, modo IBL); con la iluminación del momento da un `Scene`
+struct Stage<'a> {
+    objects: &'a [Box<dyn RayIntersect + Sync>],
+    tex_albedo: &'a (dyn Fn(&Intersect)->Vector3 + Sync),
+    ibl: IblMode,
+    ibl_strength: f32,
+    fog: Option<Fog>,
+    volumes: &'a [Volume],
+    caustics_on: bool,
+    caustics: Option<(PhotonMap, (Vector3, Vector3, f32))>, // mapa, luz (dirección, color·intensidad) e instante con que se hizo
+    caustic_targets: &'a [(Vector3, f32)],             // esferas que envuelven agua y vidrio
+    photons: usize,
+    ao: AmbientOcclusion,
+    shadows: bool,
+    spectral: bool,
+}
+
+impl Stage<'_> {
+    fn scene<'s>(&'s self, lighting: &'s Lighting) -> Scene<'s> {
+        Scene {
+            objects: self.objects, light: &lighting.light, sky: lighting.sky(), env_light: &lighting.env_light,
+            ibl: self.ibl, ibl_strength: self.ibl_strength, emission: lighting.emission(),
+            fog: self.fog.as_ref(), volumes: self.volumes, caustics: self.caustics.as_ref().map(|(m, _)| m),
+            ao: self.ao, tex_albedo: self.tex_albedo, shadows: self.shadows, spectral: self.spectral, wavelength: None,
         }
     }
+
+    /// rehace el mapa de fotones si las cáusticas están encendidas y cambió la luz o, con geometría
+    /// animada (olas del estanque, puerta), el mapa tiene más de `max_age` s de escena
+    fn update_caustics(&mut self, lighting: &Lighting, time: f32, max_age: f32) {
+        if !self.caustics_on { self.caustics = None; return; }
+        let light = &lighting.light;
+        let animated = self.objects.iter().any(|o| o.is_animated());
+        let stamp = (light.toward(Vector3::zero()).0, light.color * light.intensity, if animated { time } else { 0.0 });
+        if let Some((_, (d, c, t))) = &self.caustics
+            && (*d - stamp.0).length() < 0.005 && (*c - stamp.1).length() < 0.01
+            && (!animated || (time - *t).abs() <= max_age) { return; }
+        let map = {
+            let scene = Scene { caustics: None, ..self.scene(lighting) };
+            shoot_caustics(light, self.caustic_targets, self.photons, 1, |o, d| scene.trace(o, d, time))
+        };
+        self.caustics = Some((map, stamp));
+    }
+}
+
+// === modo por lotes: recorrido de cámara → secuencia de imágenes ===
+
+/// `--animate <recorrido> [--fps N] [--out dir (renders/)] [--size AxB] [--spp N] [--shutter grados]`;
+/// `--replay <toma>` es lo mismo con valores de calidad final (1920x1080, 16 spp, 30 fps);
+/// `--daycycle <horas/s> [--duration s]` hace correr el reloj del cielo físico (con o sin recorrido)
+struct BatchArgs {
+    path: Option<String>,
+    fps: f32,
+    out: String,
+    width: u32,
+    height: u32,
+    spp: u32,
+    shutter_angle: f32, // obturador rotativo: 180° = medio cuadro de exposición
+    daycycle: Option<f32>, // horas simuladas por segundo
+    duration: Option<f32>, // s; por defecto el recorrido o un día entero
+}
+
+fn parse_batch_args() -> Option<BatchArgs> {
+    let args: Vec<String> = std::env::args().collect();
+    let val = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1));
+    let daycycle = args.iter().any(|a| a == "--daycycle");
+    let mut b = if let Some(p) = val("--replay") {
+        BatchArgs {
+            path: Some(p.clone()), fps: 30.0, out: "renders".to_string(), width: 1920, height: 1080, spp: 16,
+            shutter_angle: 180.0, daycycle: None, duration: None,
+        }
+    } else {
+        let path = val("--animate").cloned();
+        if path.is_none() && !daycycle { return None; }
+        BatchArgs {
+            path, fps: 24.0, out: "renders".to_string(), width: 800, height: 450, spp: 4,
+            shutter_angle: 180.0, daycycle: None, duration: None,
+        }
+    };
+    if let Some(v) = val("--fps").and_then(|v| v.parse().ok()) { b.fps = v; }
+    if let Some(v) = val("--out") { b.out = v.clone(); }
+    if let Some((w, h)) = val("--size").and_then(|v| v.split_once('x'))
+        && let (Ok(w), Ok(h)) = (w.parse(), h.parse()) { b.width = w; b.height = h; }
+    if let Some(v) = val("--spp").and_then(|v| v.parse().ok()) { b.spp = v; }
+    if let Some(v) = val("--shutter").and_then(|v| v.parse().ok()) { b.shutter_angle = v; }
+    if daycycle { b.daycycle = Some(val("--daycycle").and_then(|v| v.parse().ok()).unwrap_or(1.0)); }
+    b.duration = val("--duration").and_then(|v| v.parse().ok());
+    Some(b)
+}
+
+/// primer `recordings/take_NNN.txt` libre
+fn next_take_path() -> String {
+    let _ = std::fs::create_dir_all("recordings");
+    (1..).map(|i| format!("recordings/take_{:03}.txt", i))
+        .find(|p| !std::path::Path::new(p).exists())
+        .unwrap()
+}
+
+/// renderiza la secuencia a `out/frame_NNNN.ppm`: el recorrido de cámara (o la cámara fija)
+/// y, con `--daycycle`, el cielo a la hora de cada cuadro
+fn render_animation(path: Option<&CameraPath>, cam: &mut Camera, stage: &mut Stage, lighting: &mut Lighting, b: &BatchArgs) -> std::io::Result<()> {
+    std::fs::create_dir_all(&b.out)?;
+    let duration = b.duration
+        .or(path.map(|p| p.duration()))
+        .or(b.daycycle.map(|speed| 24.0 / speed.abs().max(1e-3)))
+        .unwrap_or(0.0);
+    let count = (duration * b.fps).floor() as u32 + 1;
+    let mut fb = Framebuffer::new(b.width, b.height);
+    let shutter = (b.shutter_angle / 360.0) / b.fps;
+    if b.daycycle.is_some() && lighting.physical.is_none() { lighting.toggle_physical(); }
+    let start_hour = lighting.hour;
+    for i in 0..count {
+        let t = i as f32 / b.fps;
+        if let Some(speed) = b.daycycle {
+            lighting.set_hour(start_hour + speed * t);
+            lighting.rebuild_ibl();
+        }
+        stage.update_caustics(lighting, t, 0.0);
+        let scene = stage.scene(lighting);
+        // pose al cerrar el obturador, luego la de apertura
+        cam.motion_end = None;
+        if let Some(path) = path {
+            path.apply(t + shutter, cam);
+            let end = (cam.eye, cam.orientation);
+            path.apply(t, cam);
+            if shutter > 0.0 { cam.motion_end = Some(end); }
+        }
+        cam.shutter_open = t;
+        cam.shutter = shutter;
+        if cam.autofocus {
+            let (o, d) = cam.center_ray();
+            let hit = scene.trace(&o, &d, t);
+            if hit.hit { cam.focus_at(hit.t); }
+        }
+        fb.clear();
+        render(&mut fb, cam, &scene, b.spp, i);
+        fb.save_ppm(&format!("{}/frame_{:04}.ppm", b.out, i))?;
+        println!("cuadro {}/{}", i + 1, count);
+    }
+    Ok(())
 }
 
 // === escena: casa sencilla con 5 materiales + agua refractiva y vidrio reflectivo ===
 fn main() {
-    let (mut rl, th) = raylib::init()
-        .size(960, 540)
-        .title("Diorama Raytracer — Casa sencilla")
-        .build();
+    let batch = parse_batch_args();
 
     // carga texturas CPU
     let img_brick = Image::load_image("assets/textures/brick.png").expect("brick.png");
@@ -135,28 +582,123 @@ fn main() {
     let img_glass = Image::load_image("assets/textures/glass.png").expect("glass.png");
     let img_water = Image::load_image("assets/textures/water.png").expect("water.png");
 
-    let tex_brick = TextureCPU::from_image(&img_brick).unwrap();
-    let tex_wood  = TextureCPU::from_image(&img_wood).unwrap();
-    let tex_quartz= TextureCPU::from_image(&img_quartz).unwrap();
-    let tex_glass = TextureCPU::from_image(&img_glass).unwrap();
-    let tex_water = TextureCPU::from_image(&img_water).unwrap();
+    // ladrillo y madera viven toda la ejecución: también sirven de mapa de alturas
+    let tex_brick: &'static TextureCPU = Box::leak(Box::new(
+        TextureCPU::from_image(&img_brick).unwrap().with_filter(Filter::Trilinear)));
+    let tex_wood:  &'static TextureCPU = Box::leak(Box::new(
+        TextureCPU::from_image(&img_wood).unwrap().with_filter(Filter::Trilinear)));
+    // el piso de cuarzo se ve muy rasante: filtrado anisotrópico
+    let tex_quartz= TextureCPU::from_image(&img_quartz).unwrap().with_filter(Filter::Anisotropic(8));
+    // vidrio: una sola lámina centrada, fuera de ella color liso; agua en espejo para ocultar costuras
+    let tex_glass = TextureCPU::from_image(&img_glass).unwrap().with_filter(Filter::Trilinear)
+        .with_wrap(Wrap::Border(Color::new(235, 245, 255, 255)));
+    let tex_water = TextureCPU::from_image(&img_water).unwrap().with_filter(Filter::Trilinear)
+        .with_wrap(Wrap::Mirror);
+
+    // celosía de madera: RGB de la veta, alfa 0 en los huecos del enrejado diagonal
+    let lattice: &'static TextureCPU = {
+        let n = 128;
+        let mut data = Vec::with_capacity((n * n) as usize);
+        for y in 0..n {
+            for x in 0..n {
+                let wood = tex_wood.data[((y % tex_wood.h) * tex_wood.w + x % tex_wood.w) as usize];
+                let d1 = ((x + y) % 32) as f32 / 32.0;
+                let d2 = ((x - y).rem_euclid(32)) as f32 / 32.0;
+                let bar = d1 < 0.2 || d2 < 0.2 || x < 6 || y < 6 || x >= n - 6 || y >= n - 6;
+                data.push(Color::new(wood.r, wood.g, wood.b, if bar { 255 } else { 0 }));
+            }
+        }
+        Box::leak(Box::new(TextureCPU::from_data(n, n, data).with_filter(Filter::Trilinear)))
+    };
 
     // skybox
-    let sky = Skybox::new(
-        TextureCPU::from_image(&Image::load_image("assets/sky/nx.png").unwrap()).unwrap(),
-        TextureCPU::from_image(&Image::load_image("assets/sky/px.png").unwrap()).unwrap(),
-        TextureCPU::from_image(&Image::load_image("assets/sky/ny.png").unwrap()).unwrap(),
-        TextureCPU::from_image(&Image::load_image("assets/sky/py.png").unwrap()).unwrap(),
-        TextureCPU::from_image(&Image::load_image("assets/sky/nz.png").unwrap()).unwrap(),
-        TextureCPU::from_image(&Image::load_image("assets/sky/pz.png").unwrap()).unwrap(),
+    // clamp: el bilineal no debe mezclar el borde opuesto de cada cara
+    let sky_face = |name: &str| TextureCPU::from_image(&Image::load_image(name).unwrap()).unwrap()
+        .with_filter(Filter::Bilinear).with_wrap(Wrap::Clamp);
+    let cubemap = Skybox::new(
+        sky_face("assets/sky/nx.png"),
+        sky_face("assets/sky/px.png"),
+        sky_face("assets/sky/ny.png"),
+        sky_face("assets/sky/py.png"),
+        sky_face("assets/sky/nz.png"),
+        sky_face("assets/sky/pz.png"),
     );
+    // el archivo de escena puede cambiar el cubemap por un panorama (`env.map`, .hdr o LDR)
+    // con `env.rotation` (grados) y `env.intensity`
+    let scene_file = SceneFile::load(SCENE_PATH).unwrap_or_default();
+    let panorama = scene_file.get_str("env.map").and_then(|path| match EquirectEnv::load(path) {
+        Ok(env) => Some(env
+            .with_rotation(scene_file.get_f32("env.rotation").unwrap_or(0.0).to_radians())
+            .with_intensity(scene_file.get_f32("env.intensity").unwrap_or(1.0))),
+        Err(e) => { eprintln!("entorno: {}", e); None }
+    });
+    // o por el cielo físico de Preetham: `sky.model preetham`, `sky.time` (hora solar),
+    // `sky.latitude`, `sky.day` (del año), `sky.turbidity`, `sky.exposure`, `sky.stars` (de noche)
+    let sky_params = SkyParams {
+        latitude: scene_file.get_f32("sky.latitude").unwrap_or(40.0),
+        day: scene_file.get_f32("sky.day").unwrap_or(172.0),
+        turbidity: scene_file.get_f32("sky.turbidity").unwrap_or(3.0),
+        exposure: scene_file.get_f32("sky.exposure").unwrap_or(0.06),
+        stars: scene_file.get_f32("sky.stars").unwrap_or(1.0),
+    };
+    // ciclo de día: `day.speed` horas simuladas por segundo
+    let day_speed = scene_file.get_f32("day.speed").unwrap_or(1.0);
 
     // materiales (kd, shininess, [kd,ks,kr,kt], ior)
-    let mat_brick  = Material::new(Vector3::new(0.9,0.9,0.9), 32.0, [0.9,0.1,0.0,0.0], 1.0);
-    let mat_wood   = Material::new(Vector3::new(0.9,0.8,0.7), 32.0, [0.95,0.05,0.0,0.0], 1.0);
+    // relieve: ladrillo usa su luminancia como bump map, la madera un normal map derivado
+    let nrm_wood: &'static TextureCPU = Box::leak(Box::new(tex_wood.normal_map_from_height(4.0)));
+    let mat_brick  = Material::new(Vector3::new(0.9,0.9,0.9), 32.0, [0.9,0.1,0.0,0.0], 1.0)
+        .with_bump_map(tex_brick, 2.0);
+    let mat_wood   = Material::new(Vector3::new(0.9,0.8,0.7), 32.0, [0.95,0.05,0.0,0.0], 1.0)
+        .with_normal_map(nrm_wood);
     let mat_quartz = Material::new(Vector3::new(1.0,1.0,1.0), 64.0, [0.8,0.2,0.0,0.0], 1.0);
-    let mat_glass  = Material::new(Vector3::new(1.0,1.0,1.0), 96.0, [0.1,0.3,0.4,0.4], 1.5); // reflexión + refracción
+    let mat_glass  = Material::new(Vector3::new(1.0,1.0,1.0), 96.0, [0.1,0.3,0.4,0.4], 1.5) // reflexión + refracción
+        .with_emission(blackbody(2700.0) * 0.8); // luz cálida de adentro, solo de noche
     let mat_water  = Material::new(Vector3::new(0.8,0.9,1.0), 16.0, [0.2,0.1,0.05,0.65], 1.33);
+    // cristal del prisma: casi todo transmite; `prism.glass` = sf11 (flint, mucha dispersión),
+    // bk7 (crown) o `cauchy A B` (B en µm²)
+    let prism_glass = match scene_file.get("prism.glass") {
+        Some([name]) if name == "bk7" => Dispersion::BK7,
+        Some([name, a, b]) if name == "cauchy" => Dispersion::Cauchy { a: a.parse().unwrap_or(1.5), b: b.parse().unwrap_or(0.005) },
+        _ => Dispersion::SF11,
+    };
+    let mat_prism  = Material::new(Vector3::new(1.0,1.0,1.0), 128.0, [0.02,0.2,0.08,0.9], 1.0)
+        .with_dispersion(prism_glass);
+
+    // procedurales (sin archivos): en espacio mundo, continuas entre caras
+    let marble: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::Marble { octaves: 5, veins: 3.0 }, Space::World, 2.5,
+        Vector3::new(0.95, 0.94, 0.92), Vector3::new(0.35, 0.38, 0.45))));
+    let stone: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::Voronoi, Space::World, 9.0,
+        Vector3::new(0.25, 0.23, 0.22), Vector3::new(0.62, 0.58, 0.52))));
+    let oak: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::WoodRings { rings: 1.0, distortion: 0.6 }, Space::World, 14.0,
+        Vector3::new(0.55, 0.35, 0.18), Vector3::new(0.33, 0.19, 0.09))));
+    let mat_check: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::Checker, Space::Uv, 6.0,
+        Vector3::new(0.6, 0.12, 0.1), Vector3::new(0.9, 0.85, 0.7))));
+    let grass: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::Fbm { octaves: 6 }, Space::World, 6.0,
+        Vector3::new(0.12, 0.35, 0.08), Vector3::new(0.35, 0.6, 0.15))));
+    let dirt: &'static Procedural = Box::leak(Box::new(Procedural::new(
+        Pattern::Turbulence { octaves: 5 }, Space::World, 4.0,
+        Vector3::new(0.45, 0.33, 0.2), Vector3::new(0.25, 0.17, 0.1))));
+    let mat_lattice = Material::new(Vector3::new(0.9,0.8,0.7), 16.0, [0.95,0.05,0.0,0.0], 1.0)
+        .with_texture(lattice)
+        .with_cutout(lattice, 0.5);
+    let mat_marble = Material::new(Vector3::new(1.0,1.0,1.0), 80.0, [0.75,0.25,0.1,0.0], 1.0)
+        .with_texture(marble);
+    let mat_stone  = Material::new(Vector3::new(1.0,1.0,1.0), 8.0, [0.95,0.05,0.0,0.0], 1.0)
+        .with_texture(stone);
+    let mat_door   = Material::new(Vector3::new(1.0,1.0,1.0), 24.0, [0.9,0.1,0.0,0.0], 1.0)
+        .with_texture(oak);
+    let mat_mat    = Material::new(Vector3::new(1.0,1.0,1.0), 4.0, [1.0,0.0,0.0,0.0], 1.0)
+        .with_texture(mat_check);
+    let mat_grass  = Material::new(Vector3::new(1.0,1.0,1.0), 4.0, [1.0,0.0,0.0,0.0], 1.0)
+        .with_texture(grass);
+    let mat_dirt   = Material::new(Vector3::new(1.0,1.0,1.0), 4.0, [1.0,0.0,0.0,0.0], 1.0)
+        .with_texture(dirt);
 
     // construye casa (tamaño controlado)
     let mut objects: Vec<Box<dyn RayIntersect + Sync>> = Vec::new();
@@ -170,28 +712,28 @@ fn main() {
         ).with_tiling(5.0) // <-- repite 5x
     ));
 
-    // paredes (ladrillo)
+    // paredes (ladrillo) – tiling en mundo: el ladrillo mide igual en todas las paredes
     objects.push(Box::new(
         Cube::from_center_size(Vector3::new(0.0, 0.5, -1.5), Vector3::new(3.0, 2.0, 0.2), mat_brick)
-            .with_tiling(3.5)
+            .with_world_tiling(1.2, 1.5)
     ));
     // frontal izquierda/derecha
     objects.push(Box::new(
         Cube::from_center_size(Vector3::new(-0.9, 0.5, 1.5), Vector3::new(1.2, 2.0, 0.2), mat_brick)
-            .with_tiling(3.5)
+            .with_world_tiling(1.2, 1.5)
     ));
     objects.push(Box::new(
         Cube::from_center_size(Vector3::new( 0.9, 0.5, 1.5), Vector3::new(1.2, 2.0, 0.2), mat_brick)
-            .with_tiling(3.5)
+            .with_world_tiling(1.2, 1.5)
     ));
     // laterales
     objects.push(Box::new(
         Cube::from_center_size(Vector3::new(-1.5, 0.5, 0.0), Vector3::new(0.2, 2.0, 3.2), mat_brick)
-            .with_tiling(3.5)
+            .with_world_tiling(1.2, 1.5)
     ));
     objects.push(Box::new(
         Cube::from_center_size(Vector3::new( 1.5, 0.5, 0.0), Vector3::new(0.2, 2.0, 3.2), mat_brick)
-            .with_tiling(3.5)
+            .with_world_tiling(1.2, 1.5)
     ));
 
     // techo (madera) – mucho tiling para vetas finas
@@ -200,46 +742,176 @@ fn main() {
             .with_tiling(6.0)
     ));
 
-    // ventanas (cristal) – 1:1 o un poco de tiling si tu textura lo permite
-    objects.push(Box::new(
-        Cube::from_center_size(Vector3::new(0.0, 0.8, -1.4), Vector3::new(1.2, 0.8, 0.05), mat_glass)
-            .with_tiling(1.5)
-    ));
-    objects.push(Box::new(
-        Cube::from_center_size(Vector3::new(-1.4, 0.8, 0.0), Vector3::new(0.05, 0.8, 1.0), mat_glass)
-            .with_tiling(1.5)
-    ));
+    // `scene.props 1`: muestrario de texturas procedurales sobre el diorama de siempre
+    // (puerta de roble, escalón de mármol con tapete, chimenea de piedra con humo, césped y tierra)
+    let props = scene_file.get_f32("scene.props").unwrap_or(0.0) > 0.0;
+    if props {
+        // con `door.swing 1` la puerta se abre hacia adentro sobre la bisagra izquierda y vuelve
+        // (4 s por ciclo, se ve con el reloj de la escena corriendo)
+        let door = Cube::from_center_size(Vector3::new(0.0, 0.45, 1.5), Vector3::new(0.6, 1.5, 0.06), mat_door);
+        if scene_file.get_f32("door.swing").unwrap_or(0.0) > 0.0 {
+            let hinge = Vector3::new(-0.3, 0.0, 1.5);
+            objects.push(Box::new(Moving::new(
+                door,
+                Transform::rotate_about(hinge, Vector3::new(0.0, 1.0, 0.0), 0.0),
+                Transform::rotate_about(hinge, Vector3::new(0.0, 1.0, 0.0), 1.4),
+                (0.0, 2.0),
+            ).with_ping_pong()));
+        } else {
+            objects.push(Box::new(door));
+        }
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new(0.0, -0.25, 1.8), Vector3::new(0.7, 0.1, 0.35), mat_marble)
+        ));
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new(0.0, -0.19, 1.8), Vector3::new(0.45, 0.02, 0.25), mat_mat)
+        ));
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new(0.9, 2.0, -0.8), Vector3::new(0.4, 0.7, 0.4), mat_stone)
+        ));
 
-    // agua – un tiling moderado
-    objects.push(Box::new(
-        Cube::from_center_size(Vector3::new(0.0, -0.49, 2.6), Vector3::new(1.8, 0.12, 1.2), mat_water)
-            .with_tiling(2.5)
-    ));
+        // jardín: césped a un lado, tierra al otro
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new( 2.3, -0.29, 0.0), Vector3::new(1.2, 0.02, 4.0), mat_grass)
+        ));
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new(-2.3, -0.29, 0.0), Vector3::new(1.2, 0.02, 4.0), mat_dirt)
+        ));
+    }
 
+    // `scene.lattice 1`: celosía al costado de la casa; con sombras (`render.shadows`) su sombra
+    // recortada cae sobre la plataforma
+    if scene_file.get_f32("scene.lattice").unwrap_or(0.0) > 0.0 {
+        objects.push(Box::new(
+            Cube::from_center_size(Vector3::new(2.3, 0.2, 2.0), Vector3::new(1.2, 1.0, 0.04), mat_lattice)
+                .with_world_tiling(1.0, 1.0)
+        ));
+    }
+
+    // ventanas (cristal) – una lámina centrada (borde liso alrededor); lo especular (ventanas,
+    // estanque, prisma) deja su esfera envolvente como blanco de los fotones de las cáusticas
+    let mut caustic_targets = Vec::new();
+    for (center, size) in [
+        (Vector3::new(0.0, 0.8, -1.4), Vector3::new(1.2, 0.8, 0.05)),
+        (Vector3::new(-1.4, 0.8, 0.0), Vector3::new(0.05, 0.8, 1.0)),
+    ] {
+        let window = Cube::from_center_size(center, size, mat_glass)
+            .with_uv_transform(UvTransform::new((-0.25, -0.25), 0.0, (1.5, 1.5)));
+        caustic_targets.push(window.bounding_sphere());
+        objects.push(Box::new(window));
+    }
+
+    // agua – tiling moderado, girado para que no se alinee con el piso; olas de Gerstner
+    // en la cara de arriba (reflejos y refracción ondulan con el tiempo) y textura a la deriva
+    let pond = Cube::from_center_size(Vector3::new(0.0, -0.49, 2.6), Vector3::new(1.8, 0.12, 1.2), mat_water)
+        .with_uv_transform(UvTransform::new((0.0, 0.0), 0.6, (2.5, 1.7)));
+    caustic_targets.push(pond.bounding_sphere());
+    objects.push(Box::new(Water::new(
+        pond,
+        vec![
+            Wave::new(0.3, 0.9, 0.012, 0.5),
+            Wave::new(1.9, 0.55, 0.007, 0.5),
+            Wave::new(-0.8, 0.35, 0.004, 0.4),
+            Wave::new(2.6, 0.2, 0.002, 0.3),
+        ],
+    ).with_drift(0.02, 0.01)));
+
+    // `scene.prism 1`: prisma de cristal sobre la plataforma; separa el sol en colores (a la vista y
+    // en su cáustica)
+    if scene_file.get_f32("scene.prism").unwrap_or(0.0) > 0.0 {
+        let prism = Prism::new(Vector3::new(2.3, -0.125, 0.8), 0.3, 0.35, 0.5, 0.4, mat_prism);
+        caustic_targets.push(prism.bounding_sphere());
+        objects.push(Box::new(prism));
+    }
+
+    // humo sobre la chimenea (la columna nace en x = 0.3 de la caja, justo sobre la boca);
+    // `volume.grid` agrega otro volumen desde archivo (.txt, o crudo con `volume.dims nx ny nz`)
+    // en `volume.center` / `volume.size` con `volume.density`, p.ej. nubes sobre el diorama
+    let mut volumes = Vec::new();
+    if props {
+        volumes.push(Volume::from_center_size(DensityGrid::smoke_plume(32), Vector3::new(1.22, 3.35, -0.8), Vector3::new(1.6, 2.0, 1.2), 6.0)
+            .with_albedo(Vector3::new(0.75, 0.75, 0.78)).with_phase(0.3));
+    }
+    if let Some(path) = scene_file.get_str("volume.grid") {
+        let grid = match scene_file.get("volume.dims") {
+            Some([x, y, z]) => match (x.parse(), y.parse(), z.parse()) {
+                (Ok(x), Ok(y), Ok(z)) => DensityGrid::load_raw(path, x, y, z),
+                _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "volume.dims inválido")),
+            },
+            _ => DensityGrid::load_text(path),
+        };
+        match grid {
+            Ok(grid) => volumes.push(Volume::from_center_size(
+                grid,
+                scene_file.get_v3("volume.center").unwrap_or(Vector3::new(0.0, 4.0, 0.0)),
+                scene_file.get_v3("volume.size").unwrap_or(Vector3::new(6.0, 1.0, 6.0)),
+                scene_file.get_f32("volume.density").unwrap_or(4.0),
+            ).with_albedo(Vector3::one()).with_phase(0.6)),
+            Err(e) => eprintln!("volumen: {}", e),
+        }
+    }
 
     // función para muestrear albedo texturizado por material
     let albedo_fn = move |hit: &Intersect| -> Vector3 {
-        let (u,v) = hit.uv;
         // el tinte de material multiplica la textura
         let tint = hit.mat.diffuse;
+        // materiales con textura propia (imagen o procedural)
+        if let Some(tex) = hit.mat.texture { return tex.sample_at(hit) * tint; }
+        // cristales con dispersión: sin textura
+        if hit.mat.dispersion.is_some() { return tint; }
         // decide cuál textura usar (sencillo: por puntero de ior/albedo)
-        if (hit.mat.ior - 1.5).abs() < 0.01 { return tex_glass.sample_repeat(u,v) * tint; }
-        if (hit.mat.ior - 1.33).abs() < 0.02 { return tex_water.sample_repeat(u,v) * tint; }
+        if (hit.mat.ior - 1.5).abs() < 0.01 { return tex_glass.sample_at(hit) * tint; }
+        if (hit.mat.ior - 1.33).abs() < 0.02 { return tex_water.sample_at(hit) * tint; }
         // compara por ks alto? aquí por afinidad:
-        if hit.mat.specular_exp >= 60.0 && hit.mat.albedo[1] >= 0.2 { return tex_quartz.sample_repeat(u,v) * tint; }
+        if hit.mat.specular_exp >= 60.0 && hit.mat.albedo[1] >= 0.2 { return tex_quartz.sample_at(hit) * tint; }
         // ladrillo vs madera: heurística por tamaño del bloque en Y (techo delgado → madera)
         if hit.normal.y.abs() > 0.9 && hit.mat.albedo[0] > 0.9 && hit.mat.specular_exp < 40.0 {
-            return tex_wood.sample_repeat(u,v) * tint;
+            return tex_wood.sample_at(hit) * tint;
         }
-        tex_brick.sample_repeat(u,v) * tint
+        tex_brick.sample_at(hit) * tint
     };
 
-    // luz
-    let light = Light::new(
+    // luz (con cielo físico, el sol o la luna según la hora)
+    let lamp = Light::new(
         Vector3::new(2.5, 3.0, 3.0),
         Vector3::new(1.0, 1.0, 1.0),
         1.5
     );
+    let sun_intensity = scene_file.get_f32("sun.intensity").unwrap_or(1.5);
+    // el cielo como luz: CDF de luminancia + mapas preconvolucionados; apagada salvo que el archivo
+    // de escena traiga `env.ibl` (intensidad) o se encienda con G
+    let mut lighting = Lighting::new(
+        &cubemap, panorama.as_ref(), sky_params, lamp, sun_intensity,
+        scene_file.get_f32("sky.time").unwrap_or(15.0), scene_file.get_str("sky.model") == Some("preetham"),
+    );
+    // niebla: `fog.density` (> 0 la enciende al arrancar), `fog.albedo`, `fog.g` (fase), `fog.steps`
+    let fog_density = scene_file.get_f32("fog.density").unwrap_or(0.0);
+    let fog = Fog::new(if fog_density > 0.0 { fog_density } else { 0.08 })
+        .with_albedo(scene_file.get_v3("fog.albedo").unwrap_or(Vector3::new(0.9, 0.9, 0.9)))
+        .with_phase(scene_file.get_f32("fog.g").unwrap_or(0.6))
+        .with_steps(scene_file.get_f32("fog.steps").map_or(16, |v| v as u32));
+    // cáusticas (`caustics 1` al arrancar, `caustics.photons`): fotones hacia ventanas, estanque y prisma
+    let mut stage = Stage {
+        objects: &objects, tex_albedo: &albedo_fn,
+        ibl: if scene_file.get_f32("env.ibl").is_some() { IblMode::Prefiltered } else { IblMode::Off },
+        ibl_strength: scene_file.get_f32("env.ibl").unwrap_or(0.5),
+        fog: (fog_density > 0.0).then_some(fog), volumes: &volumes,
+        caustics_on: scene_file.get_f32("caustics").unwrap_or(0.0) > 0.0, caustics: None,
+        caustic_targets: &caustic_targets, photons: scene_file.get_f32("caustics.photons").map_or(200_000, |v| v as usize),
+        // oclusión ambiental: `ao.radius`, `ao.samples`, `ao.mode` (off / shading / only)
+        ao: AmbientOcclusion::new(
+            scene_file.get_f32("ao.radius").unwrap_or(0.5),
+            scene_file.get_f32("ao.samples").map_or(8, |v| v as u32),
+        ),
+        // sombras de la luz directa: `render.shadows 1` (apagadas, como la imagen de siempre)
+        shadows: scene_file.get_f32("render.shadows").unwrap_or(0.0) > 0.0,
+        spectral: scene_file.get_f32("render.spectral").unwrap_or(0.0) > 0.0,
+    };
+    stage.ao.mode = match scene_file.get_str("ao.mode") {
+        Some("shading") => AoMode::Shading,
+        Some("only") => AoMode::Only,
+        _ => AoMode::Off,
+    };
 
     // cámara
     let mut cam = Camera::new(
@@ -247,26 +919,258 @@ fn main() {
         Vector3::new(0.0, 0.6, 0.0),
         Vector3::new(0.0, 1.0, 0.0)
     );
+    // la cámara guardada en el archivo de escena manda sobre los valores por defecto
+    cam.read_from(&scene_file);
+
+    // por lotes no se abre ventana: la óptica (apertura, sensor...) viene del archivo de escena
+    if let Some(b) = batch {
+        let path = b.path.as_ref().map(|p| CameraPath::load(p).expect("recorrido de cámara"));
+        render_animation(path.as_ref(), &mut cam, &mut stage, &mut lighting, &b).expect("no se pudo escribir la secuencia");
+        return;
+    }
+
+    let (mut rl, th) = raylib::init()
+        .size(960, 540)
+        .title("Diorama Raytracer — Casa sencilla")
+        .build();
+    if cam.mode == CameraMode::Fly { rl.disable_cursor(); }
 
     let mut fb = Framebuffer::new(960, 540);
 
+    // muestras por pixel: 1 en vivo, más para la vista de calidad (H)
+    let mut spp: u32 = 1;
+    let mut frame: u32 = 0;
+    let mut recorder = Recorder::default();
+    let mut day_running = false;
+    // reloj de la escena (olas, puerta): quieto en 0 salvo con `scene.animate 1`, F3 o desenfoque
+    // de movimiento, para que la vista interactiva se asiente
+    let mut scene_time = 0.0_f32;
+    let mut clock_running = scene_file.get_f32("scene.animate").unwrap_or(0.0) > 0.0;
+
     rl.set_target_fps(30);
     while !rl.window_should_close() {
+        // Cielo físico (F6 enciende/apaga) y hora del día (T / Y); el IBL se recalcula al soltar
+        if rl.is_key_pressed(KeyboardKey::KEY_F6) {
+            lighting.toggle_physical();
+            day_running = false;
+        }
+        // Ciclo de día (F7 corre / pausa, enciende el cielo físico si hace falta), ventanas (F8)
+        if rl.is_key_pressed(KeyboardKey::KEY_F7) {
+            if lighting.physical.is_none() { lighting.toggle_physical(); }
+            day_running = !day_running;
+            if !day_running { lighting.rebuild_ibl(); }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_F8) { lighting.windows = !lighting.windows; }
+        // Sombras de la luz directa (F4)
+        if rl.is_key_pressed(KeyboardKey::KEY_F4) { stage.shadows = !stage.shadows; }
+        // Modo espectral (F9): una longitud de onda por muestra; conviene subir las spp
+        if rl.is_key_pressed(KeyboardKey::KEY_F9) { stage.spectral = !stage.spectral; }
+        if lighting.physical.is_some() {
+            let dh = (rl.is_key_down(KeyboardKey::KEY_Y) as i32 - rl.is_key_down(KeyboardKey::KEY_T) as i32) as f32;
+            if dh != 0.0 { lighting.set_hour(lighting.hour + dh * 0.05); }
+            if rl.is_key_released(KeyboardKey::KEY_T) || rl.is_key_released(KeyboardKey::KEY_Y) {
+                lighting.rebuild_ibl();
+            }
+            if day_running { lighting.advance(day_speed * rl.get_frame_time()); }
+        }
+        // Luz del entorno (G cicla: preconvolucionada → muestreada con sombras → apagada)
+        if rl.is_key_pressed(KeyboardKey::KEY_G) {
+            stage.ibl = match stage.ibl {
+                IblMode::Prefiltered => IblMode::Sampled,
+                IblMode::Sampled => IblMode::Off,
+                IblMode::Off => IblMode::Prefiltered,
+            };
+        }
+        // Niebla (J enciende/apaga)
+        if rl.is_key_pressed(KeyboardKey::KEY_J) {
+            stage.fog = if stage.fog.is_some() { None } else { Some(fog) };
+        }
+        // Cáusticas (U enciende/apaga); el mapa se rehace cuando cambia la luz o el instante
+        if rl.is_key_pressed(KeyboardKey::KEY_U) { stage.caustics_on = !stage.caustics_on; }
+        if rl.is_key_pressed(KeyboardKey::KEY_F3) { clock_running = !clock_running; }
+        if clock_running || cam.shutter > 0.0 { scene_time += rl.get_frame_time(); }
+        cam.shutter_open = scene_time;
+        // en vivo basta con rehacerlas cada cuarto de segundo de escena
+        stage.update_caustics(&lighting, cam.shutter_open, 0.25);
+        // Oclusión ambiental (X cicla: apagada → sombreado → solo AO); el sombreado oscurece la luz
+        // del entorno, así que sin IBL no hay nada que sombrear y se salta
+        if rl.is_key_pressed(KeyboardKey::KEY_X) { stage.ao.next_mode(); }
+        let scene = stage.scene(&lighting);
+
         // Controles:
-        // ← → → orbita yaw
-        if rl.is_key_down(KeyboardKey::KEY_LEFT)  { cam.orbit( 0.02, 0.0); }
-        if rl.is_key_down(KeyboardKey::KEY_RIGHT) { cam.orbit(-0.02, 0.0); }
-        // ↑ ↓ → orbita pitch
-        if rl.is_key_down(KeyboardKey::KEY_UP)    { cam.orbit(0.0, -0.02); }
-        if rl.is_key_down(KeyboardKey::KEY_DOWN)  { cam.orbit(0.0,  0.02); }
-        // Zoom dolly (W/S)
-        if rl.is_key_down(KeyboardKey::KEY_W)     { cam.dolly( 0.10); }
-        if rl.is_key_down(KeyboardKey::KEY_S)     { cam.dolly(-0.10); }
+        // Tab → alterna órbita / vuelo libre (en vuelo el cursor queda capturado)
+        if rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+            cam.mode = match cam.mode { CameraMode::Orbit => CameraMode::Fly, CameraMode::Fly => CameraMode::Orbit };
+            if cam.mode == CameraMode::Fly { rl.disable_cursor(); } else { rl.enable_cursor(); }
+        }
+        // Shift = rápido, Ctrl = lento
+        let speed = if rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) { 4.0 }
+            else if rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) { 0.25 } else { 1.0 };
+        match cam.mode {
+            CameraMode::Orbit => {
+                // ← → → orbita yaw
+                if rl.is_key_down(KeyboardKey::KEY_LEFT)  { cam.orbit( 0.02 * speed, 0.0); }
+                if rl.is_key_down(KeyboardKey::KEY_RIGHT) { cam.orbit(-0.02 * speed, 0.0); }
+                // ↑ ↓ → orbita pitch
+                if rl.is_key_down(KeyboardKey::KEY_UP)    { cam.orbit(0.0, -0.02 * speed); }
+                if rl.is_key_down(KeyboardKey::KEY_DOWN)  { cam.orbit(0.0,  0.02 * speed); }
+                // Zoom dolly (W/S)
+                if rl.is_key_down(KeyboardKey::KEY_W)     { cam.dolly( 0.10 * speed); }
+                if rl.is_key_down(KeyboardKey::KEY_S)     { cam.dolly(-0.10 * speed); }
+                // botón central arrastrado → mueve el centro de órbita
+                if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_MIDDLE) {
+                    let d = rl.get_mouse_delta();
+                    let k = (cam.center - cam.eye).length() * 0.002 * speed;
+                    cam.pan(-d.x * k, d.y * k);
+                }
+            }
+            CameraMode::Fly => {
+                // WASD desplaza, Q/E baja/sube (unidades por segundo)
+                let step = 2.0 * speed * rl.get_frame_time();
+                let axis = |pos: KeyboardKey, neg: KeyboardKey| {
+                    (rl.is_key_down(pos) as i32 - rl.is_key_down(neg) as i32) as f32
+                };
+                let fwd = axis(KeyboardKey::KEY_W, KeyboardKey::KEY_S);
+                let side = axis(KeyboardKey::KEY_D, KeyboardKey::KEY_A);
+                let vert = axis(KeyboardKey::KEY_E, KeyboardKey::KEY_Q);
+                cam.fly(fwd * step, side * step, vert * step);
+                // mouse (o flechas) para mirar
+                let d = rl.get_mouse_delta();
+                let turn = 0.02 * speed;
+                let yaw = d.x * 0.003 + axis(KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_LEFT) * turn;
+                let pitch = -d.y * 0.003 + axis(KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN) * turn;
+                if yaw != 0.0 || pitch != 0.0 { cam.look(yaw, pitch); }
+            }
+        }
+        // Campo de visión (- / =) y plano cercano (N / M)
+        if rl.is_key_down(KeyboardKey::KEY_MINUS) { cam.zoom( 0.01); }
+        if rl.is_key_down(KeyboardKey::KEY_EQUAL) { cam.zoom(-0.01); }
+        if rl.is_key_down(KeyboardKey::KEY_N)     { cam.near = (cam.near - 0.02).max(0.0); }
+        if rl.is_key_down(KeyboardKey::KEY_M)     { cam.near += 0.02; }
+        // Roll (Z / C) y nivelar el horizonte (L)
+        if rl.is_key_down(KeyboardKey::KEY_Z)     { cam.roll(-0.02 * speed); }
+        if rl.is_key_down(KeyboardKey::KEY_C)     { cam.roll( 0.02 * speed); }
+        if rl.is_key_pressed(KeyboardKey::KEY_L)  { cam.level(); }
+        // Profundidad de campo: apertura ([ / ]), enfoque (, / .), autofoco (F), hojas del diafragma (B)
+        if rl.is_key_down(KeyboardKey::KEY_LEFT_BRACKET)  { cam.aperture = (cam.aperture - 0.002).max(0.0); }
+        if rl.is_key_down(KeyboardKey::KEY_RIGHT_BRACKET) { cam.aperture += 0.002; }
+        if rl.is_key_down(KeyboardKey::KEY_COMMA)  { cam.autofocus = false; cam.focus_at(cam.focus_dist - 0.05); }
+        if rl.is_key_down(KeyboardKey::KEY_PERIOD) { cam.autofocus = false; cam.focus_at(cam.focus_dist + 0.05); }
+        if rl.is_key_pressed(KeyboardKey::KEY_F) { cam.autofocus = !cam.autofocus; }
+        if rl.is_key_pressed(KeyboardKey::KEY_B) {
+            cam.blades = match cam.blades { 0..=2 => 5, 5 => 6, 6 => 8, _ => 0 };
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_H) { spp = if spp == 1 { 16 } else { 1 }; }
+        // Desenfoque de movimiento (V): obturador de 180° a 30 fps; mientras está, corre el reloj de la escena
+        if rl.is_key_pressed(KeyboardKey::KEY_V) { cam.shutter = if cam.shutter > 0.0 { 0.0 } else { 1.0 / 60.0 }; }
+        if cam.autofocus {
+            let (o, d) = cam.center_ray();
+            let hit = scene.trace(&o, &d, cam.shutter_open);
+            if hit.hit { cam.focus_at(hit.t); }
+        }
+        // Estéreo (K cicla modo), separación entre ojos (9 / 0), convergencia (; / ')
+        if rl.is_key_pressed(KeyboardKey::KEY_K) { cam.next_stereo(); }
+        if rl.is_key_down(KeyboardKey::KEY_NINE) { cam.interocular = (cam.interocular - 0.002).max(0.0); }
+        if rl.is_key_down(KeyboardKey::KEY_ZERO) { cam.interocular += 0.002; }
+        if rl.is_key_down(KeyboardKey::KEY_SEMICOLON)  { cam.convergence = (cam.convergence - 0.05).max(0.1); }
+        if rl.is_key_down(KeyboardKey::KEY_APOSTROPHE) { cam.convergence += 0.05; }
+        // Proyección (O cicla, I = isométrica)
+        if rl.is_key_pressed(KeyboardKey::KEY_O) { cam.next_projection(); }
+        if rl.is_key_pressed(KeyboardKey::KEY_I) { cam.set_isometric(); }
+        // Toma en alta resolución (F12): 2:1 para panoramas 360°, 16:9 para el resto
+        if rl.is_key_pressed(KeyboardKey::KEY_F12) {
+            let (sw, sh) = if cam.projection == Projection::Equirectangular { (2048, 1024) } else { (1920, 1080) };
+            let mut shot = Framebuffer::new(sw, sh);
+            render(&mut shot, &cam, &scene, 16, 0);
+            shot.save_png("shot.png");
+        }
+        // Guardar cámara en el archivo de escena (F5)
+        if rl.is_key_pressed(KeyboardKey::KEY_F5) {
+            let mut sf = SceneFile::load(SCENE_PATH).unwrap_or_default();
+            cam.write_to(&mut sf);
+            let _ = sf.save(SCENE_PATH);
+        }
         // Guardar frame (P)
         if rl.is_key_pressed(KeyboardKey::KEY_P)  { fb.save_png("frame.png"); }
+        // Grabar la cámara (R inicia / detiene); la toma se re-renderiza con `--replay`
+        if rl.is_key_pressed(KeyboardKey::KEY_R) {
+            if recorder.is_recording() {
+                let take = recorder.stop();
+                if !take.keys.is_empty() {
+                    let path = next_take_path();
+                    match take.save(&path) {
+                        Ok(()) => println!("toma guardada en {} ({:.1} s): --replay {}", path, take.duration(), path),
+                        Err(e) => eprintln!("no se pudo guardar {}: {}", path, e),
+                    }
+                }
+            } else {
+                recorder.start();
+            }
+        }
+        recorder.capture(&cam, rl.get_frame_time());
+
+        rl.set_window_title(&th, &format!(
+            "Diorama Raytracer — Casa sencilla | fov {:.0}° ({:.0} mm) near {:.2} | apertura {:.3} foco {:.2}{} | {} spp | IBL {:?}{}{}{}{}{}{}{}{}{}{}",
+            cam.fov_y.to_degrees(), cam.focal_length(), cam.near,
+            cam.aperture, cam.focus_dist, if cam.autofocus { " (auto)" } else { "" }, spp, scene.ibl,
+            if cam.shutter > 0.0 { " | movimiento" } else { "" },
+            if clock_running { format!(" | t {:.1} s", scene_time) } else { String::new() },
+            if let Some(f) = scene.fog { format!(" | niebla {:.2}", f.density) } else { String::new() },
+            if scene.shadows { " | sombras" } else { "" },
+            if scene.caustics.is_some() { " | cáusticas" } else { "" },
+            if scene.spectral { " | espectral" } else { "" },
+            match scene.ao.mode {
+                AoMode::Off => String::new(),
+                m => format!(" | AO {:?} r {:.2} ×{}", m, scene.ao.radius, scene.ao.samples),
+            },
+            if lighting.physical.is_some() {
+                format!(" | {:02}:{:02} h{}{}", lighting.hour as u32, (lighting.hour.fract() * 60.0) as u32,
+                    if day_running { " ▶" } else { "" }, if lighting.windows { "" } else { " (ventanas apagadas)" })
+            } else { String::new() },
+            if cam.stereo == Stereo::Off { String::new() } else {
+                format!(" | estéreo {:?} ojos {:.3} conv. {:.2}", cam.stereo, cam.interocular, cam.convergence)
+            },
+            if recorder.is_recording() { " | ● REC" } else { "" }
+        ));
 
         fb.clear();
-        render(&mut fb, &cam, &light, &sky, &objects, &albedo_fn);
+        render(&mut fb, &cam, &scene, spp, frame);
+        frame = frame.wrapping_add(1);
         fb.blit(&mut rl, &th);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Grey;
+    impl Environment for Grey {
+        fn sample(&self, _: Vector3) -> Vector3 { Vector3::one() * 0.5 }
+    }
+
+    #[test]
+    fn ao_shading_darkens_ambient_without_ibl() {
+        let mat = Material::new(Vector3::one(), 10.0, [0.6, 0.0, 0.0, 0.0], 1.0);
+        // suelo con una pared pegada al punto que miramos
+        let objects: Vec<Box<dyn RayIntersect + Sync>> = vec![
+            Box::new(Cube::from_center_size(Vector3::new(0.0, -0.5, 0.0), Vector3::new(10.0, 1.0, 10.0), mat)),
+            Box::new(Cube::from_center_size(Vector3::new(0.3, 0.5, 0.0), Vector3::new(0.2, 1.0, 2.0), mat)),
+        ];
+        let light = Light::new(Vector3::new(0.0, 5.0, 0.0), Vector3::one(), 1.0);
+        let env_light = EnvLight::new(&Grey, 8, 4);
+        let tex_albedo = |_: &Intersect| Vector3::one();
+        let mut scene = Scene {
+            objects: &objects, light: &light, sky: &Grey, env_light: &env_light,
+            ibl: IblMode::Off, ibl_strength: 1.0, emission: 0.0, fog: None, volumes: &[], caustics: None,
+            ao: AmbientOcclusion::new(1.0, 64), tex_albedo: &tex_albedo,
+            shadows: false, spectral: false, wavelength: None,
+        };
+        let diff = RayDiff { dodx: Vector3::zero(), dody: Vector3::zero(), dddx: Vector3::zero(), dddy: Vector3::zero() };
+        let (ro, rd) = (Vector3::new(0.05, 3.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let open = cast_ray(&ro, &rd, &diff, &scene, 0.0, &mut Rng::new(1), 0);
+        scene.ao.mode = AoMode::Shading;
+        let occluded = cast_ray(&ro, &rd, &diff, &scene, 0.0, &mut Rng::new(1), 0);
+        assert!(occluded.x > 0.0 && occluded.x < open.x - 0.05);
+    }
+}
@@ -1,4 +1,7 @@
 use raylib::prelude::*;
+use crate::ray_intersect::Intersect;
+use crate::texture::{Texture, TextureCPU};
+use crate::dispersion::Dispersion;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Material {
@@ -6,14 +9,95 @@ pub struct Material {
     pub specular_exp: f32,        // exponente especular (Phong)
     pub albedo: [f32; 4],         // [kd, ks, kr, kt] difuso, especular, reflectividad, transparencia
     pub ior: f32,                 // índice de refracción (agua≈1.33, vidrio≈1.5)
+    pub texture: Option<&'static dyn Texture>,   // albedo propio (si no, lo decide la escena)
+    pub normal_map: Option<&'static TextureCPU>, // normal map en espacio tangente (RGB → xyz)
+    pub bump_map: Option<&'static TextureCPU>,   // mapa de alturas (luminancia)
+    pub bump_scale: f32,                         // intensidad del relieve del bump map
+    pub alpha_map: Option<&'static TextureCPU>,  // recorte por alfa (hojas, rejas, celosías)
+    pub alpha_cutoff: f32,                       // texels con alfa menor se atraviesan
+    pub emission: Vector3,                       // luz propia (ventanas encendidas), escalada por la escena
+    pub dispersion: Option<Dispersion>,          // ior según la longitud de onda (prismas, cristales)
 }
 
 impl Material {
     pub fn new(diffuse: Vector3, specular_exp: f32, albedo: [f32;4], ior: f32) -> Self {
-        Self { diffuse, specular_exp, albedo, ior }
+        Self { diffuse, specular_exp, albedo, ior, texture: None, normal_map: None, bump_map: None, bump_scale: 0.0,
+               alpha_map: None, alpha_cutoff: 0.5, emission: Vector3::zero(), dispersion: None }
     }
     pub fn black() -> Self {
-        Self { diffuse: Vector3::zero(), specular_exp: 1.0, albedo: [0.0;4], ior: 1.0 }
+        Self::new(Vector3::zero(), 1.0, [0.0;4], 1.0)
+    }
+
+    pub fn with_texture(mut self, tex: &'static dyn Texture) -> Self {
+        self.texture = Some(tex);
+        self
+    }
+
+    pub fn with_normal_map(mut self, tex: &'static TextureCPU) -> Self {
+        self.normal_map = Some(tex);
+        self
+    }
+
+    pub fn with_bump_map(mut self, tex: &'static TextureCPU, scale: f32) -> Self {
+        self.bump_map = Some(tex);
+        self.bump_scale = scale;
+        self
+    }
+
+    pub fn with_cutout(mut self, tex: &'static TextureCPU, cutoff: f32) -> Self {
+        self.alpha_map = Some(tex);
+        self.alpha_cutoff = cutoff;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Vector3) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    /// `ior` pasa a ser el de la línea D del sodio (589 nm); la refracción usa el de cada longitud de onda
+    pub fn with_dispersion(mut self, d: Dispersion) -> Self {
+        self.dispersion = Some(d);
+        self.ior = d.ior(589.3);
+        self
+    }
+
+    /// índice de refracción para la longitud de onda `lambda_nm`
+    pub fn ior_at(&self, lambda_nm: f32) -> f32 {
+        self.dispersion.map_or(self.ior, |d| d.ior(lambda_nm))
+    }
+
+    /// true si el impacto cae en un texel recortado (el rayo debe seguir de largo)
+    pub fn is_cut_out(&self, hit: &Intersect) -> bool {
+        match self.alpha_map {
+            Some(a) => a.alpha(hit.uv.0, hit.uv.1) < self.alpha_cutoff,
+            None => false,
+        }
+    }
+
+    /// normal de sombreado: perturba la normal geométrica con el normal map o el bump map
+    pub fn shading_normal(&self, hit: &Intersect) -> Vector3 {
+        let (u, v) = hit.uv;
+        let (n, t, b) = (hit.normal, hit.tangent, hit.bitangent);
+
+        if let Some(nm) = self.normal_map {
+            // convención OpenGL: verde = +v
+            let c = nm.sample(u, v);
+            let ts = Vector3::new(c.x * 2.0 - 1.0, c.y * 2.0 - 1.0, c.z * 2.0 - 1.0);
+            return (t * ts.x + b * ts.y + n * ts.z).normalized();
+        }
+
+        if let Some(hm) = self.bump_map {
+            // diferencias finitas de un texel en u y v
+            let du = 1.0 / hm.w as f32;
+            let dv = 1.0 / hm.h as f32;
+            let h0 = hm.height(u, v);
+            let dhdu = (hm.height(u + du, v) - h0) * self.bump_scale;
+            let dhdv = (hm.height(u, v + dv) - h0) * self.bump_scale;
+            return (n - t * dhdu - b * dhdv).normalized();
+        }
+
+        n
     }
 }
 
@@ -0,0 +1,109 @@
+use raylib::prelude::*;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+/// transformación rígida: gira `rotation` alrededor de `pivot` y luego traslada
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub pivot: Vector3,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self { translation: Vector3::zero(), rotation: Quaternion::identity(), pivot: Vector3::zero() }
+    }
+
+    /// giro de `angle` rad sobre el eje que pasa por `pivot` (p.ej. la bisagra de una puerta)
+    pub fn rotate_about(pivot: Vector3, axis: Vector3, angle: f32) -> Self {
+        Self { rotation: Quaternion::from_axis_angle(axis, angle), pivot, ..Self::identity() }
+    }
+
+    /// interpolación: traslación lineal, rotación esférica; para girar en arco
+    /// ambas poses deben compartir el pivote
+    pub fn lerp(&self, other: &Transform, s: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, s),
+            rotation: slerp_shortest(self.rotation, other.rotation, s),
+            pivot: self.pivot.lerp(other.pivot, s),
+        }
+    }
+
+    pub fn point(&self, p: Vector3) -> Vector3 {
+        (p - self.pivot).rotate_by(self.rotation) + self.pivot + self.translation
+    }
+
+    pub fn inverse_point(&self, p: Vector3) -> Vector3 {
+        (p - self.pivot - self.translation).rotate_by(self.rotation.inverted()) + self.pivot
+    }
+
+    pub fn vector(&self, v: Vector3) -> Vector3 { v.rotate_by(self.rotation) }
+
+    pub fn inverse_vector(&self, v: Vector3) -> Vector3 { v.rotate_by(self.rotation.inverted()) }
+}
+
+/// slerp por el camino corto (q y -q son la misma rotación)
+pub fn slerp_shortest(a: Quaternion, b: Quaternion, s: f32) -> Quaternion {
+    let dot = a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w;
+    let b = if dot < 0.0 { Quaternion::new(-b.x, -b.y, -b.z, -b.w) } else { b };
+    a.slerp(b, s).normalized()
+}
+
+/// primitiva que pasa de la pose `start` a la pose `end` durante el intervalo [t0, t1] (s);
+/// fuera del intervalo se queda en el extremo (o va y vuelve con `ping_pong`)
+pub struct Moving<T: RayIntersect> {
+    pub inner: T,
+    pub start: Transform,
+    pub end: Transform,
+    pub interval: (f32, f32),
+    pub ping_pong: bool,
+}
+
+impl<T: RayIntersect> Moving<T> {
+    pub fn new(inner: T, start: Transform, end: Transform, interval: (f32, f32)) -> Self {
+        Self { inner, start, end, interval, ping_pong: false }
+    }
+
+    /// ida y vuelta continua: start → end → start ... (periodo = 2·(t1 - t0))
+    pub fn with_ping_pong(mut self) -> Self {
+        self.ping_pong = true;
+        self
+    }
+
+    /// pose en el instante `time`
+    pub fn pose_at(&self, time: f32) -> Transform {
+        let (t0, t1) = self.interval;
+        let span = (t1 - t0).max(1e-6);
+        let mut s = (time - t0) / span;
+        if self.ping_pong {
+            s = s.rem_euclid(2.0);
+            if s > 1.0 { s = 2.0 - s; }
+        }
+        let s = s.clamp(0.0, 1.0);
+        // suavizado en los extremos (arranca y frena)
+        self.start.lerp(&self.end, s * s * (3.0 - 2.0 * s))
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Moving<T> {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3) -> Intersect {
+        self.ray_intersect_at(ro, rd, self.interval.0)
+    }
+
+    fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, time: f32) -> Intersect {
+        // el rayo pasa al espacio del objeto; al ser rígida, t no cambia
+        let xf = self.pose_at(time);
+        let lo = xf.inverse_point(*ro);
+        let ld = xf.inverse_vector(*rd);
+        let mut hit = self.inner.ray_intersect_at(&lo, &ld, time);
+        if !hit.hit { return hit; }
+        hit.point = xf.point(hit.point);
+        hit.normal = xf.vector(hit.normal);
+        hit.tangent = xf.vector(hit.tangent);
+        hit.bitangent = xf.vector(hit.bitangent);
+        hit.uv_grad = (xf.vector(hit.uv_grad.0), xf.vector(hit.uv_grad.1));
+        hit
+    }
+
+    fn is_animated(&self) -> bool { true }
+}
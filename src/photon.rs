@@ -0,0 +1,270 @@
+use raylib::prelude::*;
+use rayon::prelude::*;
+use std::collections::BinaryHeap;
+use std::f32::consts::PI;
+use crate::light::Light;
+use crate::ray_intersect::{Intersect, ortho_basis, offset_origin, reflect, refract};
+use crate::rng::Rng;
+use crate::dispersion::{RGB_WAVELENGTHS, channel_mask};
+
+/// fotón guardado: dónde cayó, cuánta potencia trae y de dónde venía
+#[derive(Clone, Copy, Debug)]
+pub struct Photon {
+    pub pos: Vector3,
+    pub power: Vector3,
+    pub dir: Vector3, // dirección de viaje al llegar
+}
+
+/// mapa de fotones de cáusticas: kd-tree balanceado implícito (la mediana de cada tramo es el nodo)
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axis: Vec<u8>, // eje de corte de cada nodo
+    targets: Vec<(Vector3, f32)>, // esferas (centro, radio) hacia donde se dispararon los fotones
+}
+
+fn coord(p: Vector3, axis: u8) -> f32 {
+    match axis { 0 => p.x, 1 => p.y, _ => p.z }
+}
+
+/// ordena el tramo como kd-tree: mediana en el medio por el eje más largo, y recursión a cada lado
+fn build(ps: &mut [Photon], axis: &mut [u8]) {
+    if ps.len() <= 1 { return; }
+    let (lo, hi) = ps.iter().fold((Vector3::one() * f32::INFINITY, Vector3::one() * f32::NEG_INFINITY), |(lo, hi), p| {
+        (Vector3::new(lo.x.min(p.pos.x), lo.y.min(p.pos.y), lo.z.min(p.pos.z)),
+         Vector3::new(hi.x.max(p.pos.x), hi.y.max(p.pos.y), hi.z.max(p.pos.z)))
+    });
+    let ext = hi - lo;
+    let a = if ext.x >= ext.y && ext.x >= ext.z { 0 } else if ext.y >= ext.z { 1 } else { 2 };
+    let mid = ps.len() / 2;
+    ps.select_nth_unstable_by(mid, |p, q| coord(p.pos, a).total_cmp(&coord(q.pos, a)));
+    axis[mid] = a;
+    let (l, r) = ps.split_at_mut(mid);
+    let (al, ar) = axis.split_at_mut(mid);
+    build(l, al);
+    build(&mut r[1..], &mut ar[1..]);
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axis = vec![0; photons.len()];
+        build(&mut photons, &mut axis);
+        Self { photons, axis, targets: Vec::new() }
+    }
+
+    pub fn with_targets(mut self, targets: &[(Vector3, f32)]) -> Self {
+        self.targets = targets.to_vec();
+        self
+    }
+
+    /// true si p cae dentro de alguna esfera a la que se dispararon fotones
+    pub fn covers(&self, p: Vector3) -> bool {
+        self.targets.iter().any(|&(c, r)| (p - c).length() <= r)
+    }
+
+    /// los `k` fotones más cercanos a p dentro de `max_r` → (índice, distancia²) en un max-heap
+    fn nearest(&self, lo: usize, hi: usize, p: Vector3, k: usize, max_d2: &mut f32, heap: &mut BinaryHeap<(u32, usize)>) {
+        if lo >= hi { return; }
+        let mid = lo + (hi - lo) / 2;
+        let node = &self.photons[mid];
+        let a = self.axis[mid];
+        let d = coord(p, a) - coord(node.pos, a);
+        // primero el lado donde cae p, después el otro si la esfera de búsqueda lo cruza
+        let (near, far) = if d < 0.0 { ((lo, mid), (mid + 1, hi)) } else { ((mid + 1, hi), (lo, mid)) };
+        self.nearest(near.0, near.1, p, k, max_d2, heap);
+        let v = node.pos - p;
+        let d2 = v.dot(v);
+        if d2 < *max_d2 {
+            // distancias no negativas: el orden de los bits es el de los f32
+            heap.push((d2.to_bits(), mid));
+            if heap.len() > k { heap.pop(); }
+            if heap.len() == k { *max_d2 = f32::from_bits(heap.peek().unwrap().0); }
+        }
+        if d * d < *max_d2 { self.nearest(far.0, far.1, p, k, max_d2, heap); }
+    }
+
+    /// irradiancia de cáusticas en p (normal n): potencia de los k fotones más cercanos / π r²
+    pub fn irradiance(&self, p: Vector3, n: Vector3, k: usize, max_r: f32) -> Vector3 {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        let mut max_d2 = max_r * max_r;
+        self.nearest(0, self.photons.len(), p, k, &mut max_d2, &mut heap);
+        if heap.len() < 4 { return Vector3::zero(); }
+        let r2 = f32::from_bits(heap.peek().unwrap().0).max(1e-8);
+        // solo los que llegan por el lado de la normal
+        let sum = heap.iter()
+            .map(|&(_, i)| &self.photons[i])
+            .filter(|ph| ph.dir.dot(n) < 0.0)
+            .fold(Vector3::zero(), |acc, ph| acc + ph.power);
+        sum / (PI * r2)
+    }
+}
+
+/// fotones por tanda: cada tanda tiene su propia semilla y corre en un hilo
+const BATCH: usize = 4096;
+
+/// dispara `count` fotones desde la luz hacia las esferas `targets` (centro, radio) que envuelven los
+/// objetos especulares (agua, vidrio) y guarda los que llegan a una superficie opaca después de al
+/// menos un rebote especular o refracción. Como la luz de la escena no decae con la distancia, cada
+/// fotón lleva la potencia de su parte de la sección de la esfera.
+pub fn shoot_caustics(
+    light: &Light, targets: &[(Vector3, f32)], count: usize, seed: u64,
+    trace: impl Fn(&Vector3, &Vector3) -> Intersect + Sync,
+) -> PhotonMap {
+    let per_target = count / targets.len().max(1);
+    let flux = light.color * light.intensity;
+    // (esfera, tanda): el resultado no depende de cómo se repartan entre los hilos
+    let batches: Vec<(usize, usize)> = (0..targets.len())
+        .flat_map(|i| (0..per_target.div_ceil(BATCH)).map(move |b| (i, b)))
+        .collect();
+    let stored: Vec<Photon> = batches.into_par_iter().flat_map_iter(|(i, batch)| {
+        let (center, radius) = targets[i];
+        let mut rng = Rng::new(seed ^ ((i as u64) << 32 | batch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let mut stored = Vec::new();
+        let (ldir, _) = light.toward(center);
+        let (t, b) = ortho_basis(&ldir);
+        let power = flux * (PI * radius * radius / per_target as f32);
+        for _ in 0..BATCH.min(per_target - batch * BATCH) {
+            // punto uniforme en el disco de la esfera visto desde la luz
+            let (u1, u2) = rng.next_2d();
+            let (r, phi) = (radius * u1.sqrt(), 2.0 * PI * u2);
+            let on_disk = center + t * (r * phi.cos()) + b * (r * phi.sin());
+            let (mut ro, mut rd) = match light.direction {
+                Some(_) => (on_disk + ldir * (radius + 50.0), -ldir),
+                None => (light.pos, (on_disk - light.pos).normalized()),
+            };
+            let mut p = power;
+            let mut specular = false;
+            let mut channel = None; // canal al que quedó reducido por dispersión
+            for _ in 0..6 {
+                let hit = trace(&ro, &rd);
+                if !hit.hit { break; }
+                let (kr, kt) = (hit.mat.albedo[2], hit.mat.albedo[3]);
+                // superficie opaca: su parte difusa recibe la cáustica
+                if kt <= 0.0 && specular { stored.push(Photon { pos: hit.point, power: p, dir: rd }); }
+                // ruleta rusa entre reflejar, transmitir o absorber (la potencia no cambia)
+                let u = rng.next_f32();
+                let n = hit.normal;
+                let next = if u < kr {
+                    reflect(&rd, &n).normalized()
+                } else if u < kr + kt {
+                    // tinte del material transparente, como en las sombras
+                    p = Vector3::new(p.x * hit.mat.diffuse.x, p.y * hit.mat.diffuse.y, p.z * hit.mat.diffuse.z);
+                    // con dispersión el fotón se queda con un canal al azar (triple potencia) y su longitud de onda
+                    let ior = match hit.mat.dispersion {
+                        None => hit.mat.ior,
+                        Some(d) => {
+                            let c = match channel {
+                                Some(c) => c,
+                                None => {
+                                    let c = ((rng.next_f32() * 3.0) as usize).min(2);
+                                    let m = channel_mask(c) * 3.0;
+                                    p = Vector3::new(p.x * m.x, p.y * m.y, p.z * m.z);
+                                    channel = Some(c);
+                                    c
+                                }
+                            };
+                            d.ior(RGB_WAVELENGTHS[c])
+                        }
+                    };
+                    // reflexión total interna: sigue reflejado
+                    refract(&rd, &n, ior).unwrap_or_else(|| reflect(&rd, &n).normalized())
+                } else {
+                    break;
+                };
+                specular = true;
+                ro = offset_origin(&hit.point, &n, &next);
+                rd = next;
+            }
+        }
+        stored
+    }).collect();
+    PhotonMap::new(stored).with_targets(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_photons(n: usize, seed: u64) -> Vec<Photon> {
+        let mut rng = Rng::new(seed);
+        (0..n).map(|_| Photon {
+            pos: Vector3::new(rng.next_f32(), rng.next_f32() * 0.5, rng.next_f32() * 2.0),
+            power: Vector3::new(rng.next_f32(), rng.next_f32(), rng.next_f32()),
+            dir: Vector3::new(0.0, -1.0, 0.0),
+        }).collect()
+    }
+
+    fn key(p: &Photon) -> [u32; 6] {
+        [p.pos.x, p.pos.y, p.pos.z, p.power.x, p.power.y, p.power.z].map(f32::to_bits)
+    }
+
+    #[test]
+    fn build_keeps_every_photon() {
+        let photons = random_photons(1000, 7);
+        let map = PhotonMap::new(photons.clone());
+        let mut a: Vec<_> = photons.iter().map(key).collect();
+        let mut b: Vec<_> = map.photons.iter().map(key).collect();
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let photons = random_photons(2000, 3);
+        let map = PhotonMap::new(photons.clone());
+        let mut rng = Rng::new(11);
+        for _ in 0..50 {
+            let p = Vector3::new(rng.next_f32(), rng.next_f32() * 0.5, rng.next_f32() * 2.0);
+            let (k, max_r) = (16, 0.3);
+            let mut heap = BinaryHeap::new();
+            let mut max_d2 = max_r * max_r;
+            map.nearest(0, map.photons.len(), p, k, &mut max_d2, &mut heap);
+            let mut got: Vec<f32> = heap.iter().map(|&(d, _)| f32::from_bits(d)).collect();
+            got.sort_by(f32::total_cmp);
+
+            let mut all: Vec<f32> = photons.iter().map(|ph| { let v = ph.pos - p; v.dot(v) })
+                .filter(|&d2| d2 < max_r * max_r).collect();
+            all.sort_by(f32::total_cmp);
+            all.truncate(k);
+            assert_eq!(got, all);
+
+            // irradiancia: potencia de esos k fotones / π r²
+            if all.len() >= 4 {
+                let r2 = *all.last().unwrap();
+                let sum = photons.iter().filter(|ph| { let v = ph.pos - p; v.dot(v) <= r2 })
+                    .fold(Vector3::zero(), |acc, ph| acc + ph.power);
+                let want = sum / (PI * r2);
+                let e = map.irradiance(p, Vector3::new(0.0, 1.0, 0.0), k, max_r);
+                assert!((e - want).length() <= 1e-3 * want.length().max(1.0), "{:?} vs {:?}", e, want);
+            }
+        }
+    }
+
+    #[test]
+    fn shooting_is_repeatable_and_lands_under_the_glass() {
+        use crate::cube::Cube;
+        use crate::material::Material;
+        use crate::ray_intersect::RayIntersect;
+
+        let glass = Material::new(Vector3::one(), 1.0, [0.0, 0.0, 0.0, 1.0], 1.0);
+        let floor = Material::new(Vector3::one(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0);
+        let objects = [
+            Cube::from_center_size(Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.2, 1.0), glass),
+            Cube::from_center_size(Vector3::new(0.0, -0.5, 0.0), Vector3::new(20.0, 1.0, 20.0), floor),
+        ];
+        let trace = |o: &Vector3, d: &Vector3| {
+            objects.iter().map(|c| c.ray_intersect(o, d)).filter(|i| i.hit)
+                .min_by(|a, b| a.t.total_cmp(&b.t)).unwrap_or_else(Intersect::empty)
+        };
+        let sun = Light::sun(Vector3::new(0.0, 1.0, 0.0), Vector3::one(), 1.0);
+        let targets = [(Vector3::new(0.0, 1.0, 0.0), 0.75)];
+        let a = shoot_caustics(&sun, &targets, 10_000, 5, trace);
+        let b = shoot_caustics(&sun, &targets, 10_000, 5, trace);
+        assert!(!a.photons.is_empty());
+        assert_eq!(a.photons.iter().map(key).collect::<Vec<_>>(), b.photons.iter().map(key).collect::<Vec<_>>());
+        // luz vertical y vidrio sin desvío: solo cae la sombra de la losa, en el suelo
+        for ph in &a.photons {
+            assert!(ph.pos.y.abs() < 1e-3 && ph.pos.x.abs() <= 0.5 + 1e-3 && ph.pos.z.abs() <= 0.5 + 1e-3);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use raylib::prelude::*;
+use crate::ray_intersect::{Intersect, RayIntersect, ortho_basis};
+use crate::material::Material;
+
+/// prisma triangular: triángulo isósceles (base `width`, alto `height`, vértice arriba)
+/// extruido `depth` a lo largo de Z y girado `yaw` alrededor de Y; se corta como intersección de planos
+pub struct Prism {
+    planes: [(Vector3, f32); 5], // (normal hacia afuera, n·x del plano)
+    bound: (Vector3, f32),       // esfera que lo envuelve (centro, radio)
+    pub mat: Material,
+}
+
+impl Prism {
+    pub fn new(center: Vector3, width: f32, height: f32, depth: f32, yaw: f32, mat: Material) -> Self {
+        let (s, c) = yaw.sin_cos();
+        let rot = |v: Vector3| Vector3::new(c * v.x + s * v.z, v.y, -s * v.x + c * v.z);
+        // caras laterales: perpendiculares a los lados que suben al vértice (0, h/2)
+        let left = Vector3::new(-height, width * 0.5, 0.0).normalized();
+        let right = Vector3::new(height, width * 0.5, 0.0).normalized();
+        let local = [
+            (Vector3::new(0.0, -1.0, 0.0), height * 0.5),
+            (left, left.y * height * 0.5),
+            (right, right.y * height * 0.5),
+            (Vector3::new(0.0, 0.0, 1.0), depth * 0.5),
+            (Vector3::new(0.0, 0.0, -1.0), depth * 0.5),
+        ];
+        let planes = local.map(|(n, d)| { let n = rot(n); (n, d + n.dot(center)) });
+        let bound = (center, Vector3::new(width, height, depth).length() * 0.5);
+        Self { planes, bound, mat }
+    }
+
+    /// esfera (centro, radio) que envuelve el prisma
+    pub fn bounding_sphere(&self) -> (Vector3, f32) { self.bound }
+}
+
+impl RayIntersect for Prism {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3) -> Intersect {
+        // tramo dentro de todos los semiespacios
+        let (mut t0, mut t1) = (f32::NEG_INFINITY, f32::INFINITY);
+        let (mut n0, mut n1) = (Vector3::zero(), Vector3::zero());
+        for &(n, d) in &self.planes {
+            let denom = n.dot(*rd);
+            let dist = d - n.dot(*ro);
+            if denom.abs() < 1e-8 {
+                if dist < 0.0 { return Intersect::empty(); }
+                continue;
+            }
+            let t = dist / denom;
+            if denom < 0.0 { if t > t0 { t0 = t; n0 = n; } } else if t < t1 { t1 = t; n1 = n; }
+        }
+        if t0 > t1 || t1 <= 0.0 { return Intersect::empty(); }
+        // desde dentro (refracción) el impacto es la cara de salida
+        let (t, n) = if t0 > 0.0 { (t0, n0) } else { (t1, n1) };
+        let p = *ro + *rd * t;
+        let (tu, tv) = ortho_basis(&n);
+        Intersect::new(p, n, t, self.mat, (p.dot(tu), p.dot(tv)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_prism(yaw: f32) -> Prism { Prism::new(Vector3::zero(), 1.0, 1.0, 1.0, yaw, Material::black()) }
+
+    fn assert_near(v: Vector3, want: Vector3) {
+        assert!((v - want).length() < 1e-4, "{:?} vs {:?}", v, want);
+    }
+
+    #[test]
+    fn hits_slanted_side_from_above() {
+        let hit = unit_prism(0.0).ray_intersect(&Vector3::new(0.1, 5.0, 0.0), &Vector3::new(0.0, -1.0, 0.0));
+        assert!(hit.hit && (hit.t - 4.7).abs() < 1e-4, "t = {}", hit.t);
+        assert_near(hit.normal, Vector3::new(1.0, 0.5, 0.0).normalized());
+
+        let hit = unit_prism(0.0).ray_intersect(&Vector3::new(-5.0, -0.4, 0.0), &Vector3::new(1.0, 0.0, 0.0));
+        assert!(hit.hit && (hit.t - 4.55).abs() < 1e-4, "t = {}", hit.t);
+        assert_near(hit.normal, Vector3::new(-1.0, 0.5, 0.0).normalized());
+    }
+
+    #[test]
+    fn inside_ray_hits_exit_face() {
+        let hit = unit_prism(0.0).ray_intersect(&Vector3::zero(), &Vector3::new(0.0, -1.0, 0.0));
+        assert!(hit.hit && (hit.t - 0.5).abs() < 1e-5);
+        assert_near(hit.normal, Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn misses_past_the_ends_and_follows_yaw() {
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        assert!(!unit_prism(0.0).ray_intersect(&Vector3::new(0.1, 5.0, 0.6), &down).hit);
+        // girado 90°: el largo queda sobre X y la sección sobre Z
+        let turned = unit_prism(std::f32::consts::FRAC_PI_2);
+        let hit = turned.ray_intersect(&Vector3::new(0.4, 5.0, 0.1), &down);
+        assert!(hit.hit && (hit.t - 4.7).abs() < 1e-4, "t = {}", hit.t);
+        assert!(!turned.ray_intersect(&Vector3::new(0.6, 5.0, 0.1), &down).hit);
+    }
+}
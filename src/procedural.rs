@@ -0,0 +1,180 @@
+use raylib::prelude::*;
+use crate::ray_intersect::Intersect;
+use crate::texture::Texture;
+
+/// dónde se evalúa el patrón
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Space {
+    /// (u, v, 0) de la primitiva
+    Uv,
+    /// posición del impacto en mundo: continuidad entre caras (textura sólida);
+    /// en objetos en movimiento, la de su pose de reposo (la veta viaja con el objeto)
+    World,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    Checker,
+    /// ruido de Perlin fractal (fBm)
+    Fbm { octaves: u32 },
+    /// suma de |ruido| por octava
+    Turbulence { octaves: u32 },
+    /// vetas: sin(x·frecuencia + turbulencia)
+    Marble { octaves: u32, veins: f32 },
+    /// anillos concéntricos alrededor del eje Y, deformados con ruido
+    WoodRings { rings: f32, distortion: f32 },
+    /// celdas de Voronoi (distancia al borde de la celda)
+    Voronoi,
+}
+
+/// textura procedural: mezcla dos colores según un patrón escalar en [0,1]
+#[derive(Clone, Copy, Debug)]
+pub struct Procedural {
+    pub pattern: Pattern,
+    pub space: Space,
+    pub scale: f32,
+    pub color_a: Vector3,
+    pub color_b: Vector3,
+}
+
+impl Procedural {
+    pub fn new(pattern: Pattern, space: Space, scale: f32, color_a: Vector3, color_b: Vector3) -> Self {
+        Self { pattern, space, scale, color_a, color_b }
+    }
+
+    /// valor escalar del patrón en el punto p (ya escalado)
+    pub fn value(&self, p: Vector3) -> f32 {
+        match self.pattern {
+            Pattern::Checker => {
+                let s = p.x.floor() as i32 + p.y.floor() as i32 + p.z.floor() as i32;
+                if s.rem_euclid(2) == 0 { 0.0 } else { 1.0 }
+            }
+            // el fBm rara vez pasa de ±0.5: se estira para aprovechar el rango
+            Pattern::Fbm { octaves } => (fbm(p, octaves) + 0.5).clamp(0.0, 1.0),
+            Pattern::Turbulence { octaves } => (turbulence(p, octaves) * 2.0).min(1.0),
+            Pattern::Marble { octaves, veins } => {
+                0.5 + 0.5 * (p.x * veins + 6.0 * turbulence(p, octaves)).sin()
+            }
+            Pattern::WoodRings { rings, distortion } => {
+                let r = (p.x * p.x + p.z * p.z).sqrt() + distortion * perlin(p * 0.5);
+                let f = (r * rings).fract();
+                // anillos con borde marcado (madera tardía más delgada)
+                f * f * (3.0 - 2.0 * f)
+            }
+            Pattern::Voronoi => {
+                let (d1, d2) = voronoi(p);
+                ((d2 - d1) * 2.0).min(1.0)
+            }
+        }
+    }
+}
+
+impl Texture for Procedural {
+    fn sample_at(&self, hit: &Intersect) -> Vector3 {
+        let p = match self.space {
+            Space::Uv => Vector3::new(hit.uv.0, hit.uv.1, 0.0),
+            Space::World => hit.object_point,
+        } * self.scale;
+        self.color_a.lerp(self.color_b, self.value(p))
+    }
+}
+
+// === ruido de Perlin (versión "improved", Perlin 2002) ===
+
+/// permutación fija (barajada con un LCG para no cargar una tabla literal)
+fn perm(i: i32) -> i32 {
+    static PERM: std::sync::OnceLock<[u8; 256]> = std::sync::OnceLock::new();
+    let p = PERM.get_or_init(|| {
+        let mut p = [0u8; 256];
+        for (i, v) in p.iter_mut().enumerate() { *v = i as u8; }
+        let mut s: u32 = 0x9E37_79B9;
+        for i in (1..256).rev() {
+            s = s.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            p.swap(i, (s >> 8) as usize % (i + 1));
+        }
+        p
+    });
+    p[(i & 255) as usize] as i32
+}
+
+fn grad(hash: i32, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: f32) -> f32 { t * t * t * (t * (t * 6.0 - 15.0) + 10.0) }
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }
+
+/// ruido de Perlin 3D en [-1,1] (aprox.)
+pub fn perlin(p: Vector3) -> f32 {
+    let (xi, yi, zi) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let (x, y, z) = (p.x - p.x.floor(), p.y - p.y.floor(), p.z - p.z.floor());
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = perm(xi) + yi;
+    let (aa, ab) = (perm(a) + zi, perm(a + 1) + zi);
+    let b = perm(xi + 1) + yi;
+    let (ba, bb) = (perm(b) + zi, perm(b + 1) + zi);
+
+    lerp(
+        lerp(
+            lerp(grad(perm(aa), x, y, z), grad(perm(ba), x - 1.0, y, z), u),
+            lerp(grad(perm(ab), x, y - 1.0, z), grad(perm(bb), x - 1.0, y - 1.0, z), u),
+            v,
+        ),
+        lerp(
+            lerp(grad(perm(aa + 1), x, y, z - 1.0), grad(perm(ba + 1), x - 1.0, y, z - 1.0), u),
+            lerp(grad(perm(ab + 1), x, y - 1.0, z - 1.0), grad(perm(bb + 1), x - 1.0, y - 1.0, z - 1.0), u),
+            v,
+        ),
+        w,
+    )
+}
+
+/// fBm: suma de octavas (lacunaridad 2, ganancia 0.5), en [-1,1] aprox.
+pub fn fbm(p: Vector3, octaves: u32) -> f32 {
+    let (mut sum, mut amp, mut freq, mut norm) = (0.0, 1.0, 1.0, 0.0);
+    for _ in 0..octaves.max(1) {
+        sum += perlin(p * freq) * amp;
+        norm += amp;
+        amp *= 0.5;
+        freq *= 2.0;
+    }
+    sum / norm
+}
+
+/// turbulencia: como fBm pero con |ruido|, en [0,1] aprox.
+pub fn turbulence(p: Vector3, octaves: u32) -> f32 {
+    let (mut sum, mut amp, mut freq, mut norm) = (0.0, 1.0, 1.0, 0.0);
+    for _ in 0..octaves.max(1) {
+        sum += perlin(p * freq).abs() * amp;
+        norm += amp;
+        amp *= 0.5;
+        freq *= 2.0;
+    }
+    sum / norm
+}
+
+/// punto característico de una celda entera (determinista)
+fn cell_point(x: i32, y: i32, z: i32) -> Vector3 {
+    let h = |k: i32| perm(perm(perm(x + k) + y) + z) as f32 / 255.0;
+    Vector3::new(x as f32 + h(0), y as f32 + h(71), z as f32 + h(137))
+}
+
+/// distancias al primer y segundo punto de Voronoi más cercanos
+pub fn voronoi(p: Vector3) -> (f32, f32) {
+    let (cx, cy, cz) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let (mut d1, mut d2) = (f32::INFINITY, f32::INFINITY);
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let d = (cell_point(cx + dx, cy + dy, cz + dz) - p).length();
+                if d < d1 { d2 = d1; d1 = d; } else if d < d2 { d2 = d; }
+            }
+        }
+    }
+    (d1, d2)
+}
@@ -4,24 +4,93 @@ use crate::material::Material;
 #[derive(Clone, Copy)]
 pub struct Intersect {
     pub point: Vector3,
+    pub object_point: Vector3, // punto en espacio del objeto (= point si no se mueve)
     pub normal: Vector3,
     pub t: f32,
     pub hit: bool,
     pub mat: Material,
     pub uv: (f32, f32),   // para texturizar
+    pub tangent: Vector3,   // dP/du (espacio mundo)
+    pub bitangent: Vector3, // dP/dv (espacio mundo)
+    pub uv_grad: (Vector3, Vector3), // gradientes de u y v respecto a la posición en mundo
+    pub duvdx: (f32, f32),  // huella del pixel en uv (diferenciales de rayo)
+    pub duvdy: (f32, f32),
 }
 
 impl Intersect {
     pub fn new(point: Vector3, normal: Vector3, t: f32, mat: Material, uv:(f32,f32)) -> Self {
-        Self { point, normal, t, hit: true, mat, uv }
+        let (tangent, bitangent) = ortho_basis(&normal);
+        Self { point, object_point: point, normal, t, hit: true, mat, uv, tangent, bitangent,
+               uv_grad: (tangent, bitangent), duvdx: (0.0, 0.0), duvdy: (0.0, 0.0) }
+    }
+    /// fija tangente/bitangente a partir de la parametrización uv de la primitiva
+    pub fn with_tangents(mut self, tangent: Vector3, bitangent: Vector3) -> Self {
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+        self
+    }
+    /// cuánto cambian u y v al moverse en mundo (incluye tiling y rotación de uv)
+    pub fn with_uv_gradients(mut self, du: Vector3, dv: Vector3) -> Self {
+        self.uv_grad = (du, dv);
+        self
+    }
+    /// proyecta las diferenciales del rayo sobre el plano tangente y las pasa a uv
+    pub fn set_footprint(&mut self, rd: &Vector3, diff: &RayDiff) {
+        let (dpdx, dpdy) = diff.at_hit(rd, self.t, &self.normal);
+        let to_uv = |dp: Vector3| (dp.dot(self.uv_grad.0), dp.dot(self.uv_grad.1));
+        self.duvdx = to_uv(dpdx);
+        self.duvdy = to_uv(dpdy);
     }
     pub fn empty() -> Self {
-        Self { point: Vector3::zero(), normal: Vector3::zero(), t: 0.0, hit: false, mat: Material::black(), uv:(0.0,0.0) }
+        Self { point: Vector3::zero(), object_point: Vector3::zero(), normal: Vector3::zero(), t: 0.0, hit: false, mat: Material::black(), uv:(0.0,0.0),
+               tangent: Vector3::zero(), bitangent: Vector3::zero(),
+               uv_grad: (Vector3::zero(), Vector3::zero()), duvdx: (0.0, 0.0), duvdy: (0.0, 0.0) }
+    }
+}
+
+/// diferenciales de rayo (Igehy): derivadas de origen y dirección respecto a x/y de pantalla
+#[derive(Clone, Copy)]
+pub struct RayDiff {
+    pub dodx: Vector3, pub dody: Vector3,
+    pub dddx: Vector3, pub dddy: Vector3,
+}
+
+impl RayDiff {
+    /// transfiere al plano del impacto: dP = dO + t·dD + D·dt
+    pub fn at_hit(&self, rd: &Vector3, t: f32, n: &Vector3) -> (Vector3, Vector3) {
+        let dn = rd.dot(*n);
+        let transfer = |d_o: Vector3, d_d: Vector3| {
+            let dp = d_o + d_d * t;
+            if dn.abs() < 1e-6 { return dp; }
+            dp - *rd * (dp.dot(*n) / dn)
+        };
+        (transfer(self.dodx, self.dddx), transfer(self.dody, self.dddy))
+    }
+
+    /// diferenciales tras reflejar en una superficie plana
+    pub fn reflected(&self, rd: &Vector3, t: f32, n: &Vector3) -> Self {
+        let (dodx, dody) = self.at_hit(rd, t, n);
+        Self { dodx, dody, dddx: reflect(&self.dddx, n), dddy: reflect(&self.dddy, n) }
+    }
+
+    /// diferenciales tras refractar (aprox.: la dirección se escala por eta)
+    pub fn refracted(&self, rd: &Vector3, t: f32, n: &Vector3, eta: f32) -> Self {
+        let (dodx, dody) = self.at_hit(rd, t, n);
+        Self { dodx, dody, dddx: self.dddx * eta, dddy: self.dddy * eta }
     }
 }
 
 pub trait RayIntersect {
     fn ray_intersect(&self, ro: &Vector3, rd: &Vector3) -> Intersect;
+
+    /// intersección con la geometría tal como está en el instante `time` (s);
+    /// lo estático ignora el tiempo
+    fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, _time: f32) -> Intersect {
+        self.ray_intersect(ro, rd)
+    }
+
+    /// true si la forma cambia con el tiempo (lo que dependa de ella hay que rehacerlo)
+    fn is_animated(&self) -> bool { false }
 }
 
 pub const ORIGIN_BIAS: f32 = 1e-4;
@@ -31,6 +100,14 @@ pub fn offset_origin(p: &Vector3, n: &Vector3, dir: &Vector3) -> Vector3 {
     if dir.dot(*n) < 0.0 { *p - off } else { *p + off }
 }
 
+/// base ortonormal arbitraria alrededor de n (para primitivas sin uv propia)
+pub fn ortho_basis(n: &Vector3) -> (Vector3, Vector3) {
+    let a = if n.x.abs() > 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let t = a.cross(*n).normalized();
+    let b = n.cross(t);
+    (t, b)
+}
+
 pub fn reflect(i: &Vector3, n: &Vector3) -> Vector3 { *i - *n * 2.0 * i.dot(*n) }
 
 pub fn refract(i: &Vector3, n: &Vector3, ior: f32) -> Option<Vector3> {
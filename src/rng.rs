@@ -0,0 +1,40 @@
+/// generador pseudoaleatorio pequeño (PCG32): determinista por semilla, sin dependencias
+#[derive(Clone, Copy, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut r = Self { state: 0 };
+        r.next_u32();
+        r.state = r.state.wrapping_add(seed);
+        r.next_u32();
+        r
+    }
+
+    /// semilla por pixel y cuadro (cada pixel tiene su propia secuencia)
+    pub fn for_pixel(x: u32, y: u32, frame: u32) -> Self {
+        let h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (frame as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+        Self::new(h)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// uniforme en [0,1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn next_2d(&mut self) -> (f32, f32) {
+        (self.next_f32(), self.next_f32())
+    }
+}
@@ -0,0 +1,128 @@
+use raylib::prelude::*;
+use std::fs;
+use std::io;
+
+/// archivo de escena en texto plano: una entrada por línea, `clave valor valor ...`
+/// (líneas vacías y las que empiezan con `#` se ignoran, pero se conservan al guardar)
+#[derive(Default)]
+pub struct SceneFile {
+    entries: Vec<Entry>,
+    lines: Vec<Line>, // el archivo en orden, para reescribirlo tal como estaba
+}
+
+struct Entry {
+    key: String,
+    vals: Vec<String>,
+    text: Option<String>, // línea original, mientras los valores no cambien
+}
+
+enum Line {
+    Text(String), // comentario o línea vacía
+    Entry(usize),
+}
+
+impl SceneFile {
+    pub fn parse(text: &str) -> Self {
+        let mut sf = Self::default();
+        for raw in text.lines() {
+            let line = raw.trim();
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some(key) if !line.starts_with('#') => {
+                    sf.lines.push(Line::Entry(sf.entries.len()));
+                    sf.entries.push(Entry {
+                        key: key.to_string(), vals: parts.map(str::to_string).collect(), text: Some(raw.to_string()),
+                    });
+                }
+                _ => sf.lines.push(Line::Text(raw.to_string())),
+            }
+        }
+        sf
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// el texto leído con solo las entradas cambiadas reescritas y las nuevas al final
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Text(t) => out.push_str(t),
+                Line::Entry(i) => match &self.entries[*i] {
+                    Entry { text: Some(t), .. } => out.push_str(t),
+                    Entry { key, vals, .. } => {
+                        out.push_str(key);
+                        for v in vals { out.push(' '); out.push_str(v); }
+                    }
+                },
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// reemplaza (o agrega) una entrada
+    pub fn set(&mut self, key: &str, vals: &[String]) {
+        match self.entries.iter_mut().find(|e| e.key == key) {
+            Some(e) => if e.vals != vals { e.vals = vals.to_vec(); e.text = None; },
+            None => self.push(key, vals),
+        }
+    }
+
+    /// agrega una entrada al final aunque la clave ya exista
+    pub fn push(&mut self, key: &str, vals: &[String]) {
+        self.lines.push(Line::Entry(self.entries.len()));
+        self.entries.push(Entry { key: key.to_string(), vals: vals.to_vec(), text: None });
+    }
+
+    pub fn set_f32(&mut self, key: &str, v: f32) { self.set(key, &[v.to_string()]); }
+
+    pub fn set_v3(&mut self, key: &str, v: Vector3) {
+        self.set(key, &[v.x.to_string(), v.y.to_string(), v.z.to_string()]);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[String]> {
+        self.entries.iter().find(|e| e.key == key).map(|e| e.vals.as_slice())
+    }
+
+    /// todas las entradas con esa clave, en orden (claves repetidas, p.ej. `key` de un recorrido)
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a [String]> + 'a {
+        self.entries.iter().filter(move |e| e.key == key).map(|e| e.vals.as_slice())
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.first().map(String::as_str)
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get_str(key)?.parse().ok()
+    }
+
+    pub fn get_v3(&self, key: &str) -> Option<Vector3> {
+        let v = self.get(key)?;
+        if v.len() < 3 { return None; }
+        Some(Vector3::new(v[0].parse().ok()?, v[1].parse().ok()?, v[2].parse().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_comments_order_and_untouched_lines() {
+        let text = "# cámara\ncam.fov   60\n\ncam.eye 0 1 5  \n# cielo\nsky.time 15\n";
+        let mut sf = SceneFile::parse(text);
+        assert_eq!(sf.to_text(), text);
+        sf.set_f32("sky.time", 18.5);
+        sf.set_f32("cam.fov", 60.0);
+        sf.set_f32("fog.density", 0.1);
+        assert_eq!(sf.to_text(), "# cámara\ncam.fov   60\n\ncam.eye 0 1 5  \n# cielo\nsky.time 18.5\nfog.density 0.1\n");
+    }
+}
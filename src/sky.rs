@@ -0,0 +1,178 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use crate::environment::Environment;
+use crate::color::{blackbody, xyz_to_rgb};
+
+/// cielo analítico de Preetham (1999): luminancia y cromaticidad por funciones de Perez
+/// según turbidez y posición del sol
+pub struct PhysicalSky {
+    pub sun_dir: Vector3,   // hacia el sol (normalizado)
+    pub turbidity: f32,     // 2 = muy limpio, 10 = brumoso
+    pub exposure: f32,      // kcd/m² → valores de pantalla
+    pub stars: f32,         // brillo del campo de estrellas (0 = sin estrellas)
+    perez: [[f32; 5]; 3],   // A..E para Y, x, y
+    zenith: [f32; 3],       // Yz, xz, yz
+    norm: [f32; 3],         // F(0, θs) de cada canal
+}
+
+/// Perez: (1 + A·e^(B/cosθ))·(1 + C·e^(Dγ) + E·cos²γ)
+fn perez(c: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    (1.0 + c[0] * (c[1] / cos_theta.max(0.01)).exp()) * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos().powi(2))
+}
+
+/// Yxy → RGB lineal (sRGB/Rec.709)
+fn yxy_to_rgb(y_lum: f32, x: f32, y: f32) -> Vector3 {
+    if y <= 0.0 { return Vector3::zero(); }
+    xyz_to_rgb(Vector3::new(x * y_lum / y, y_lum, (1.0 - x - y) * y_lum / y))
+}
+
+/// hash entero → [0,1)
+fn hash01(mut h: u32) -> f32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// campo de estrellas fijo: celdas de ~0.25° en lat-long, a lo sumo una estrella puntual por celda
+fn star_field(d: Vector3) -> Vector3 {
+    const CELLS: f32 = 1440.0; // celdas por vuelta
+    let lon = d.x.atan2(-d.z) / (2.0 * PI) + 0.5;
+    let lat = d.y.clamp(-1.0, 1.0).asin() / PI + 0.5;
+    let (cu, cv) = ((lon * CELLS).floor(), (lat * CELLS * 0.5).floor());
+    let seed = (cu as u32).wrapping_mul(73_856_093) ^ (cv as u32).wrapping_mul(19_349_663);
+    // menos celdas con estrella cerca del polo (las celdas se achican)
+    if hash01(seed) > 0.05 * (lat - 0.5).abs().mul_add(-2.0, 1.0).max(0.1) { return Vector3::zero(); }
+    // posición de la estrella dentro de la celda y distancia angular (en celdas)
+    let (su, sv) = (hash01(seed ^ 0xa511_e9b3), hash01(seed ^ 0x63d8_3595));
+    let du = (lon * CELLS - cu - su) * (lat * PI - PI * 0.5).cos();
+    let dv = lat * CELLS * 0.5 - cv - sv;
+    let r2 = du * du + dv * dv;
+    if r2 > 0.09 { return Vector3::zero(); }
+    // brillo con muchas débiles y pocas fuertes; color de 3000 K a 12000 K
+    let mag = hash01(seed ^ 0x2c1b_3c6d).powi(6) * 4.0 + 0.05;
+    blackbody(3000.0 + 9000.0 * hash01(seed ^ 0x297a_2d39)) * (mag * (1.0 - r2 / 0.09))
+}
+
+impl PhysicalSky {
+    pub fn new(sun_dir: Vector3, turbidity: f32) -> Self {
+        let mut s = Self {
+            sun_dir, turbidity, exposure: 0.06, stars: 0.0,
+            perez: [[0.0; 5]; 3], zenith: [0.0; 3], norm: [1.0; 3],
+        };
+        s.set_sun(sun_dir);
+        s
+    }
+
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// recalcula coeficientes para un sol nuevo (el modelo vale con el sol sobre el horizonte:
+    /// más abajo se evalúa en el horizonte y `sun_fade` apaga el cielo)
+    pub fn set_sun(&mut self, sun_dir: Vector3) {
+        self.sun_dir = sun_dir.normalized();
+        let t = self.turbidity;
+        let theta_s = self.sun_dir.y.clamp(0.02, 1.0).acos();
+        self.perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let (th, th2, th3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let yz = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let xz = t * t * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let yz_c = t * t * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+        self.zenith = [yz.max(0.0), xz, yz_c];
+        for i in 0..3 { self.norm[i] = perez(&self.perez[i], 1.0, theta_s); }
+    }
+
+    /// 1 de día, 0 con el sol bajo el horizonte (crepúsculo suave)
+    pub fn sun_fade(&self) -> f32 {
+        let e = ((self.sun_dir.y + 0.1) / 0.15).clamp(0.0, 1.0);
+        e * e * (3.0 - 2.0 * e)
+    }
+
+    /// color (transmitancia RGB) de la luz solar directa tras cruzar la atmósfera:
+    /// Rayleigh + aerosoles (Ångström) con la masa de aire de Kasten-Young
+    pub fn sun_color(&self) -> Vector3 {
+        let zenith_deg = self.sun_dir.y.clamp(-1.0, 1.0).acos().to_degrees().min(93.0);
+        let m = 1.0 / (zenith_deg.to_radians().cos() + 0.50572 * (96.07995 - zenith_deg).powf(-1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let tau = |lambda_um: f32| {
+            let rayleigh = (-0.008735 * lambda_um.powf(-4.08) * m).exp();
+            let aerosol = (-beta * lambda_um.powf(-1.3) * m).exp();
+            rayleigh * aerosol
+        };
+        Vector3::new(tau(0.65), tau(0.57), tau(0.475)) * self.sun_fade()
+    }
+
+    /// dirección hacia el sol para una hora solar (0-24), latitud (grados) y día del año
+    pub fn sun_direction(hour: f32, latitude: f32, day: f32) -> Vector3 {
+        let decl = 23.44f32.to_radians() * (2.0 * PI * (284.0 + day) / 365.0).sin();
+        let lat = latitude.to_radians();
+        let ha = (hour - 12.0) * 15f32.to_radians();
+        let sin_alt = lat.sin() * decl.sin() + lat.cos() * decl.cos() * ha.cos();
+        let alt = sin_alt.clamp(-1.0, 1.0).asin();
+        // acimut desde el norte, hacia el este
+        let cos_az = ((decl.sin() - alt.sin() * lat.sin()) / (alt.cos() * lat.cos()).max(1e-4)).clamp(-1.0, 1.0);
+        let az = if ha > 0.0 { 2.0 * PI - cos_az.acos() } else { cos_az.acos() };
+        // convención de la escena: norte = -Z, este = +X, arriba = +Y
+        Vector3::new(alt.cos() * az.sin(), alt.sin(), -alt.cos() * az.cos())
+    }
+}
+
+impl Environment for PhysicalSky {
+    fn sample(&self, dir: Vector3) -> Vector3 {
+        let d = dir.normalized();
+        // de noche: azul muy oscuro más estrellas (se ven cuando el cielo de día se apaga)
+        let night = 1.0 - self.sun_fade();
+        let mut extra = Vector3::new(0.004, 0.006, 0.014) * night;
+        if self.stars > 0.0 && d.y > 0.0 { extra += star_field(d) * (self.stars * night); }
+        // bajo el horizonte: el cielo del horizonte reflejado en un suelo gris
+        let (d, ground) = if d.y < 0.0 { (Vector3::new(d.x, 0.001, d.z).normalized(), 0.3) } else { (d, 1.0) };
+        let cos_theta = d.y;
+        let gamma = d.dot(self.sun_dir).clamp(-1.0, 1.0).acos();
+        let ch = |i: usize| self.zenith[i] * perez(&self.perez[i], cos_theta, gamma) / self.norm[i];
+        let sky = yxy_to_rgb(ch(0), ch(1), ch(2)) * (self.exposure * self.sun_fade() * ground);
+        // disco solar (~0.5°, algo agrandado para que se vea)
+        if ground == 1.0 && gamma < 0.01 {
+            return sky + self.sun_color() * 20.0 + extra;
+        }
+        Vector3::new(sky.x.max(0.0), sky.y.max(0.0), sky.z.max(0.0)) + extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zenith_matches_preetham_values() {
+        // T = 3 con el sol en el cenit: Yz ≈ 29.48 kcd/m², (x, y) ≈ (0.2707, 0.2824)
+        let sky = PhysicalSky::new(Vector3::new(0.0, 1.0, 0.0), 3.0);
+        let [yz, xz, yc] = sky.zenith;
+        assert!((yz - 29.477).abs() < 0.05 && (xz - 0.2707).abs() < 1e-3 && (yc - 0.2824).abs() < 1e-3, "{:?}", sky.zenith);
+        // el cenit se oscurece al bajar el sol
+        let low = PhysicalSky::new(Vector3::new(0.0, 0.5, 0.866), 3.0);
+        assert!(low.zenith[0] < yz);
+    }
+
+    #[test]
+    fn zenith_sample_is_the_zenith_color_and_blue() {
+        let sky = PhysicalSky::new(Vector3::new(0.0, 1.0, 1.0), 3.0);
+        let up = sky.sample(Vector3::new(0.0, 1.0, 0.0));
+        let [yz, xz, yc] = sky.zenith;
+        let want = yxy_to_rgb(yz, xz, yc) * sky.exposure;
+        assert!((up - want).length() < 1e-4 * want.length(), "{:?} vs {:?}", up, want);
+        assert!(up.z > up.x);
+    }
+}
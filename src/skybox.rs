@@ -44,12 +44,12 @@ impl Skybox {
         };
 
         match face {
-            0 => self.nx.sample_repeat(u,v),
-            1 => self.px.sample_repeat(u,v),
-            2 => self.ny.sample_repeat(u,v),
-            3 => self.py.sample_repeat(u,v),
-            4 => self.nz.sample_repeat(u,v),
-            _ => self.pz.sample_repeat(u,v),
+            0 => self.nx.sample(u,v),
+            1 => self.px.sample(u,v),
+            2 => self.ny.sample(u,v),
+            3 => self.py.sample(u,v),
+            4 => self.nz.sample(u,v),
+            _ => self.pz.sample(u,v),
         }
     }
 }
@@ -0,0 +1,44 @@
+use raylib::prelude::*;
+use crate::color::{BlackbodySpectrum, LAMBDA_MAX, LAMBDA_MIN, rgb_to_illuminant, rgb_to_reflectance};
+use crate::environment::Environment;
+use crate::fog::Fog;
+use crate::light::Light;
+
+// el modo espectral sigue cada camino en una sola longitud de onda: los colores se reemplazan por
+// grises con el valor de su espectro en λ, y la cuenta RGB de siempre (productos canal a canal)
+// queda hecha en esa longitud de onda
+
+/// longitud de onda de la muestra `i` de `n`, estratificada en el rango (pdf uniforme)
+pub fn wavelength(i: u32, n: u32, u: f32) -> f32 {
+    LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f32 + u) / n.max(1) as f32
+}
+
+/// reflectancia (albedo, tinte) en λ, como gris
+pub fn reflectance(rgb: Vector3, lambda: f32) -> Vector3 { Vector3::one() * rgb_to_reflectance(rgb, lambda) }
+
+/// radiancia (cielo, emisión) en λ, como gris
+pub fn radiance(rgb: Vector3, lambda: f32) -> Vector3 { Vector3::one() * rgb_to_illuminant(rgb, lambda) }
+
+/// el entorno visto en una sola longitud de onda
+pub struct EnvAt<'a> {
+    pub inner: &'a dyn Environment,
+    pub lambda: f32,
+}
+
+impl Environment for EnvAt<'_> {
+    fn sample(&self, dir: Vector3) -> Vector3 { radiance(self.inner.sample(dir), self.lambda) }
+}
+
+/// la luz en λ: espectro de Planck si tiene temperatura (`planck`, calculado una vez), si no su color
+pub fn light_at(light: &Light, planck: Option<&BlackbodySpectrum>, lambda: f32) -> Light {
+    let color = match planck {
+        Some(p) => Vector3::one() * p.at(lambda),
+        None => radiance(light.color, lambda),
+    };
+    Light { color, ..*light }
+}
+
+/// la niebla en λ
+pub fn fog_at(fog: &Fog, lambda: f32) -> Fog {
+    Fog { albedo: reflectance(fog.albedo, lambda), ..*fog }
+}
@@ -1,9 +1,81 @@
 use raylib::prelude::*;
+use crate::ray_intersect::Intersect;
 
+/// filtro de muestreo (por textura)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    /// bilineal + interpolación entre niveles de mip
+    Trilinear,
+    /// trilineal con hasta N muestras a lo largo del eje mayor de la huella
+    Anisotropic(u32),
+}
+
+/// cualquier fuente de color evaluable en un impacto (imagen o procedural)
+pub trait Texture: Sync + std::fmt::Debug {
+    fn sample_at(&self, hit: &Intersect) -> Vector3;
+}
+
+/// direccionamiento fuera de [0,1]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+    /// repite alternando espejo: 0→1→0→1…
+    Mirror,
+    /// fuera del rango devuelve un color fijo
+    Border(Color),
+}
+
+/// transformación de uv por primitiva: escala por eje → rotación → desplazamiento
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvTransform {
+    pub offset: (f32, f32),
+    pub rotation: f32, // radianes
+    pub scale: (f32, f32),
+}
+
+impl UvTransform {
+    pub fn identity() -> Self {
+        Self { offset: (0.0, 0.0), rotation: 0.0, scale: (1.0, 1.0) }
+    }
+
+    /// la escala se aleja de 0 como en `Cube::with_tiling` (el signo se conserva: espejo)
+    pub fn new(offset: (f32, f32), rotation: f32, scale: (f32, f32)) -> Self {
+        let clamp = |s: f32| if s.abs() < 0.001 { 0.001_f32.copysign(s) } else { s };
+        Self { offset, rotation, scale: (clamp(scale.0), clamp(scale.1)) }
+    }
+
+    pub fn apply(&self, u: f32, v: f32) -> (f32, f32) {
+        let [[a, b], [c, d]] = self.jacobian();
+        (a * u + b * v + self.offset.0, c * u + d * v + self.offset.1)
+    }
+
+    /// parte lineal [[du'/du, du'/dv], [dv'/du, dv'/dv]]
+    pub fn jacobian(&self) -> [[f32; 2]; 2] {
+        let (s, c) = self.rotation.sin_cos();
+        let (su, sv) = self.scale;
+        [[c * su, -s * sv], [s * su, c * sv]]
+    }
+}
+
+/// nivel de la cadena de mips (el nivel 0 vive en `TextureCPU::data`)
+#[derive(Debug)]
+struct Mip {
+    w: i32,
+    h: i32,
+    data: Vec<Color>,
+}
+
+#[derive(Debug)]
 pub struct TextureCPU {
     pub w: i32,
     pub h: i32,
     pub data: Vec<Color>, // row-major
+    pub filter: Filter,
+    pub wrap: Wrap,
+    mips: Vec<Mip>,       // niveles 1.. (cada uno la mitad del anterior)
 }
 
 impl TextureCPU {
@@ -11,16 +83,242 @@ impl TextureCPU {
         // get_image_data() -> ImageColors, lo convertimos a Vec<Color>
         let colors = img.get_image_data();
         let data: Vec<Color> = colors.to_vec();
-        Some(Self { w: img.width(), h: img.height(), data })
+        Some(Self::from_data(img.width(), img.height(), data))
+    }
+
+    /// construye la textura y su cadena de mips (box filter 2x2)
+    pub fn from_data(w: i32, h: i32, data: Vec<Color>) -> Self {
+        let mut mips: Vec<Mip> = Vec::new();
+        let (mut pw, mut ph) = (w, h);
+        while pw > 1 || ph > 1 {
+            let prev: &[Color] = mips.last().map(|m| m.data.as_slice()).unwrap_or(&data);
+            let (nw, nh) = ((pw / 2).max(1), (ph / 2).max(1));
+            let mut next = Vec::with_capacity((nw * nh) as usize);
+            for y in 0..nh {
+                for x in 0..nw {
+                    let (mut r, mut g, mut b, mut a) = (0u32, 0u32, 0u32, 0u32);
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let sx = (x * 2 + dx).min(pw - 1);
+                        let sy = (y * 2 + dy).min(ph - 1);
+                        let c = prev[(sy * pw + sx) as usize];
+                        r += c.r as u32; g += c.g as u32; b += c.b as u32; a += c.a as u32;
+                    }
+                    next.push(Color::new((r / 4) as u8, (g / 4) as u8, (b / 4) as u8, (a / 4) as u8));
+                }
+            }
+            mips.push(Mip { w: nw, h: nh, data: next });
+            pw = nw; ph = nh;
+        }
+        Self { w, h, data, filter: Filter::Nearest, wrap: Wrap::Repeat, mips }
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn mip_levels(&self) -> usize { self.mips.len() + 1 }
+
+    fn level(&self, l: usize) -> (i32, i32, &[Color]) {
+        if l == 0 { (self.w, self.h, &self.data) } else { let m = &self.mips[l - 1]; (m.w, m.h, &m.data) }
+    }
+
+    /// aplica el direccionamiento; None = fuera del rango con modo borde
+    fn address(&self, w: i32, h: i32, x: i32, y: i32) -> Option<(i32, i32)> {
+        match self.wrap {
+            Wrap::Repeat => Some((x.rem_euclid(w), y.rem_euclid(h))),
+            Wrap::Clamp => Some((x.clamp(0, w - 1), y.clamp(0, h - 1))),
+            Wrap::Mirror => {
+                let m = |i: i32, n: i32| { let k = i.rem_euclid(2 * n); if k < n { k } else { 2 * n - 1 - k } };
+                Some((m(x, w), m(y, h)))
+            }
+            Wrap::Border(_) => {
+                if x < 0 || y < 0 || x >= w || y >= h { None } else { Some((x, y)) }
+            }
+        }
     }
 
-    pub fn sample_repeat(&self, mut u: f32, mut v: f32) -> Vector3 {
-        u = u.fract(); if u < 0.0 { u += 1.0; }
-        v = v.fract(); if v < 0.0 { v += 1.0; }
-        let x = (u * self.w as f32).floor().clamp(0.0, (self.w - 1) as f32) as i32;
-        let y = ((1.0 - v) * self.h as f32).floor().clamp(0.0, (self.h - 1) as f32) as i32;
-        let idx = (y * self.w + x) as usize;
-        let c = self.data[idx];
+    /// texel (x,y) del nivel l según el modo de direccionamiento
+    fn texel(&self, l: usize, x: i32, y: i32) -> Vector3 {
+        let (w, h, data) = self.level(l);
+        let c = match self.address(w, h, x, y) {
+            Some((x, y)) => data[(y * w + x) as usize],
+            None => if let Wrap::Border(b) = self.wrap { b } else { unreachable!() },
+        };
         Vector3::new(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0)
     }
+
+    fn nearest(&self, l: usize, u: f32, v: f32) -> Vector3 {
+        let (w, h, _) = self.level(l);
+        let x = (u * w as f32).floor() as i32;
+        let y = ((1.0 - v) * h as f32).floor() as i32;
+        self.texel(l, x, y)
+    }
+
+    fn bilinear(&self, l: usize, u: f32, v: f32) -> Vector3 {
+        let (w, h, _) = self.level(l);
+        // centros de texel en (i + 0.5)
+        let fx = u * w as f32 - 0.5;
+        let fy = (1.0 - v) * h as f32 - 0.5;
+        let (x0, y0) = (fx.floor() as i32, fy.floor() as i32);
+        let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+        let top = self.texel(l, x0, y0) * (1.0 - tx) + self.texel(l, x0 + 1, y0) * tx;
+        let bot = self.texel(l, x0, y0 + 1) * (1.0 - tx) + self.texel(l, x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bot * ty
+    }
+
+    /// bilineal en un lod continuo, mezclando los dos niveles vecinos
+    fn trilinear(&self, lod: f32, u: f32, v: f32) -> Vector3 {
+        let max = (self.mip_levels() - 1) as f32;
+        let lod = lod.clamp(0.0, max);
+        let l0 = lod.floor() as usize;
+        let l1 = (l0 + 1).min(self.mip_levels() - 1);
+        let f = lod - l0 as f32;
+        let a = self.bilinear(l0, u, v);
+        if f <= 0.0 || l0 == l1 { return a; }
+        a * (1.0 - f) + self.bilinear(l1, u, v) * f
+    }
+
+    /// muestreo en el nivel 0 con el filtro y direccionamiento de la textura (sin huella)
+    pub fn sample(&self, u: f32, v: f32) -> Vector3 {
+        match self.filter {
+            Filter::Nearest => self.nearest(0, u, v),
+            _ => self.bilinear(0, u, v),
+        }
+    }
+
+    /// muestreo con gradientes de uv por pixel (como textureGrad): elige el mip según la huella
+    pub fn sample_grad(&self, u: f32, v: f32, duvdx: (f32, f32), duvdy: (f32, f32)) -> Vector3 {
+        // huella en texels del nivel 0
+        let (w, h) = (self.w as f32, self.h as f32);
+        let ax = Vector2::new(duvdx.0 * w, duvdx.1 * h);
+        let ay = Vector2::new(duvdy.0 * w, duvdy.1 * h);
+        let (lx, ly) = (ax.length(), ay.length());
+
+        match self.filter {
+            Filter::Nearest => self.nearest(0, u, v),
+            Filter::Bilinear => self.bilinear(0, u, v),
+            Filter::Trilinear => self.trilinear(lx.max(ly).max(1e-8).log2(), u, v),
+            Filter::Anisotropic(max_n) => {
+                let (major, minor, axis) = if lx >= ly { (lx, ly, duvdx) } else { (ly, lx, duvdy) };
+                let n = (major / minor.max(1e-8)).ceil().clamp(1.0, max_n.max(1) as f32);
+                // el lod lo dicta el eje menor (ampliado si se recortó el número de muestras)
+                let lod = (major / n).max(1e-8).log2();
+                let count = n as i32;
+                let mut acc = Vector3::zero();
+                for i in 0..count {
+                    // muestras repartidas a lo largo del eje mayor, centradas en (u,v)
+                    let s = (i as f32 + 0.5) / n - 0.5;
+                    acc += self.trilinear(lod, u + axis.0 * s, v + axis.1 * s);
+                }
+                acc / n
+            }
+        }
+    }
+
+    /// canal alfa en [0,1] (nivel 0, vecino más cercano: el recorte no debe encogerse con los mips)
+    pub fn alpha(&self, u: f32, v: f32) -> f32 {
+        let x = (u * self.w as f32).floor() as i32;
+        let y = ((1.0 - v) * self.h as f32).floor() as i32;
+        let c = match self.address(self.w, self.h, x, y) {
+            Some((x, y)) => self.data[(y * self.w + x) as usize],
+            None => if let Wrap::Border(b) = self.wrap { b } else { unreachable!() },
+        };
+        c.a as f32 / 255.0
+    }
+
+    /// altura en [0,1] (luminancia) para bump mapping
+    pub fn height(&self, u: f32, v: f32) -> f32 {
+        let c = self.sample(u, v);
+        0.299 * c.x + 0.587 * c.y + 0.114 * c.z
+    }
+
+    /// genera un normal map (espacio tangente) a partir de la luminancia como altura
+    pub fn normal_map_from_height(&self, strength: f32) -> Self {
+        let lum = |x: i32, y: i32| -> f32 {
+            let c = self.data[(y.rem_euclid(self.h) * self.w + x.rem_euclid(self.w)) as usize];
+            (0.299 * c.r as f32 + 0.587 * c.g as f32 + 0.114 * c.b as f32) / 255.0
+        };
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..self.h {
+            for x in 0..self.w {
+                // y de imagen crece hacia abajo, v hacia arriba
+                let dx = (lum(x + 1, y) - lum(x - 1, y)) * 0.5 * strength;
+                let dy = (lum(x, y - 1) - lum(x, y + 1)) * 0.5 * strength;
+                let n = Vector3::new(-dx, -dy, 1.0).normalized();
+                data.push(Color::new(
+                    ((n.x * 0.5 + 0.5) * 255.0) as u8,
+                    ((n.y * 0.5 + 0.5) * 255.0) as u8,
+                    ((n.z * 0.5 + 0.5) * 255.0) as u8,
+                    255,
+                ));
+            }
+        }
+        Self::from_data(self.w, self.h, data).with_filter(self.filter)
+    }
+}
+
+impl Texture for TextureCPU {
+    /// albedo en el punto de impacto usando sus gradientes de uv
+    fn sample_at(&self, hit: &Intersect) -> Vector3 {
+        self.sample_grad(hit.uv.0, hit.uv.1, hit.duvdx, hit.duvdy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grey(v: u8) -> Color { Color::new(v, v, v, 255) }
+
+    #[test]
+    fn mips_halve_with_box_average() {
+        let data = [0, 40, 80, 120, 200, 240, 40, 80].map(grey).to_vec();
+        let t = TextureCPU::from_data(4, 2, data);
+        assert_eq!(t.mip_levels(), 3);
+        let (w, h, l1) = t.level(1);
+        assert_eq!((w, h), (2, 1));
+        assert_eq!((l1[0].r, l1[1].r), (120, 80));
+        let (w, h, l2) = t.level(2);
+        assert_eq!((w, h, l2[0].r), (1, 1, 100));
+
+        // lados impares: cada nivel redondea hacia abajo sin bajar de 1
+        let t = TextureCPU::from_data(5, 3, vec![grey(0); 15]);
+        let sizes: Vec<_> = (0..t.mip_levels()).map(|l| { let (w, h, _) = t.level(l); (w, h) }).collect();
+        assert_eq!(sizes, [(5, 3), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn wrap_modes_address_out_of_range_uvs() {
+        // 4x1 con vecino más cercano: u = 1.125 cae en x = 4, u = -0.125 en x = -1
+        let red = |wrap: Wrap, u: f32| {
+            let t = TextureCPU::from_data(4, 1, [0, 85, 170, 255].map(grey).to_vec()).with_wrap(wrap);
+            (t.sample(u, 0.5).x * 255.0).round() as u8
+        };
+        assert_eq!((red(Wrap::Repeat, 1.125), red(Wrap::Repeat, -0.125)), (0, 255));
+        assert_eq!((red(Wrap::Clamp, 1.125), red(Wrap::Clamp, -0.125)), (255, 0));
+        assert_eq!((red(Wrap::Mirror, 1.125), red(Wrap::Mirror, 1.875), red(Wrap::Mirror, -0.125)), (255, 0, 0));
+        let border = Wrap::Border(grey(9));
+        assert_eq!((red(border, 1.125), red(border, -0.125), red(border, 0.6)), (9, 9, 170));
+    }
+
+    #[test]
+    fn trilinear_level_follows_footprint() {
+        // tablero 8x8: el nivel 0 alterna blanco y negro, el 1 ya es gris
+        let data = (0..64).map(|i| grey(if (i % 8 + i / 8) % 2 == 0 { 255 } else { 0 })).collect();
+        let t = TextureCPU::from_data(8, 8, data).with_filter(Filter::Trilinear);
+        let (u, v) = (0.5 / 8.0, 1.0 - 0.5 / 8.0); // centro del texel (0,0), blanco
+        let at = |texels: f32| t.sample_grad(u, v, (texels / 8.0, 0.0), (0.0, 0.0)).x;
+        let gray = 127.0 / 255.0;
+        assert!((at(1.0) - 1.0).abs() < 1e-5);
+        assert!((at(2.0) - gray).abs() < 1e-5);
+        assert!((at(2f32.sqrt()) - (1.0 + gray) * 0.5).abs() < 1e-4);
+        // huella mayor que la textura: el último nivel
+        assert!((at(64.0) - gray).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,205 @@
+use raylib::prelude::*;
+use std::fs;
+use std::io;
+use crate::procedural::fbm;
+use crate::rng::Rng;
+
+/// grilla 3D de densidades (x más rápido, luego y, luego z), muestreo trilineal en [0,1]³
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    data: Vec<f32>,
+    pub max: f32, // mayorante para el delta tracking
+}
+
+fn invalid(msg: String) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, msg) }
+
+/// una grilla vacía no se puede muestrear
+fn check_dims(path: &str, nx: usize, ny: usize, nz: usize) -> io::Result<()> {
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(invalid(format!("{}: dimensiones {}x{}x{} vacías", path, nx, ny, nz)));
+    }
+    Ok(())
+}
+
+impl DensityGrid {
+    pub fn from_data(nx: usize, ny: usize, nz: usize, data: Vec<f32>) -> Self {
+        let max = data.iter().cloned().fold(0.0, f32::max);
+        Self { nx, ny, nz, data, max }
+    }
+
+    /// texto: `nx ny nz` y luego nx·ny·nz valores separados por espacios; `#` comenta hasta fin de línea
+    pub fn load_text(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut nums = text.lines()
+            .map(|l| l.split('#').next().unwrap_or(""))
+            .flat_map(str::split_whitespace);
+        let mut dim = || nums.next().and_then(|v| v.parse::<usize>().ok())
+            .ok_or_else(|| invalid(format!("{}: faltan las dimensiones", path)));
+        let (nx, ny, nz) = (dim()?, dim()?, dim()?);
+        check_dims(path, nx, ny, nz)?;
+        let data = nums.map(|v| v.parse::<f32>()
+            .map_err(|_| invalid(format!("{}: valor no numérico '{}'", path, v))))
+            .collect::<io::Result<Vec<f32>>>()?;
+        if data.len() != nx * ny * nz {
+            return Err(invalid(format!("{}: {} valores, se esperaban {}", path, data.len(), nx * ny * nz)));
+        }
+        Ok(Self::from_data(nx, ny, nz, data))
+    }
+
+    /// binario crudo sin cabecera: u8 (0-255 → 0-1) o f32 little-endian, según el tamaño del archivo
+    pub fn load_raw(path: &str, nx: usize, ny: usize, nz: usize) -> io::Result<Self> {
+        check_dims(path, nx, ny, nz)?;
+        let bytes = fs::read(path)?;
+        let n = nx * ny * nz;
+        let data = if bytes.len() == n {
+            bytes.iter().map(|&b| b as f32 / 255.0).collect()
+        } else if bytes.len() == 4 * n {
+            bytes.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+        } else {
+            return Err(invalid(format!("{}: {} bytes no cuadran con {}x{}x{}", path, bytes.len(), nx, ny, nz)));
+        };
+        Ok(Self::from_data(nx, ny, nz, data))
+    }
+
+    /// humo de chimenea: columna que sale del punto (0.3, 0, 0.5), se ensancha al subir,
+    /// se tuerce con el viento hacia +X y se deshilacha con ruido; se desvanece arriba
+    pub fn smoke_plume(n: usize) -> Self {
+        let (nx, ny, nz) = (n, 2 * n, n);
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let (u, v, w) = ((x as f32 + 0.5) / nx as f32, (y as f32 + 0.5) / ny as f32, (z as f32 + 0.5) / nz as f32);
+                    let cx = 0.3 + 0.45 * v * v;
+                    let radius = 0.06 + 0.22 * v;
+                    let noise = fbm(Vector3::new(u * 4.0, v * 6.0, w * 4.0), 4);
+                    let r = ((u - cx).powi(2) + (w - 0.5).powi(2)).sqrt() / radius + noise * 0.6;
+                    let fade = (1.0 - v).powf(1.5);
+                    data.push(((1.0 - r).max(0.0) * fade * 2.0).min(1.0));
+                }
+            }
+        }
+        Self::from_data(nx, ny, nz, data)
+    }
+
+    fn at(&self, x: usize, y: usize, z: usize) -> f32 { self.data[(z * self.ny + y) * self.nx + x] }
+
+    /// densidad trilineal en coordenadas normalizadas (fuera de [0,1]³ = 0)
+    pub fn sample(&self, p: Vector3) -> f32 {
+        if !(0.0..=1.0).contains(&p.x) || !(0.0..=1.0).contains(&p.y) || !(0.0..=1.0).contains(&p.z) { return 0.0; }
+        let cell = |v: f32, n: usize| {
+            let f = (v * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            let i = (f as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), f - i as f32)
+        };
+        let (x0, x1, tx) = cell(p.x, self.nx);
+        let (y0, y1, ty) = cell(p.y, self.ny);
+        let (z0, z1, tz) = cell(p.z, self.nz);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let plane = |z: usize| lerp(
+            lerp(self.at(x0, y0, z), self.at(x1, y0, z), tx),
+            lerp(self.at(x0, y1, z), self.at(x1, y1, z), tx),
+            ty,
+        );
+        lerp(plane(z0), plane(z1), tz)
+    }
+}
+
+/// medio participante heterogéneo dentro de una caja (como `Cube`): humo, nubes
+pub struct Volume {
+    pub grid: DensityGrid,
+    pub min: Vector3,
+    pub max: Vector3,
+    pub density: f32,    // σt donde la grilla vale 1
+    pub albedo: Vector3, // σs/σt
+    pub g: f32,          // anisotropía de Henyey-Greenstein
+}
+
+impl Volume {
+    pub fn from_center_size(grid: DensityGrid, center: Vector3, size: Vector3, density: f32) -> Self {
+        let half = size * 0.5;
+        Self { grid, min: center - half, max: center + half, density, albedo: Vector3::new(0.8, 0.8, 0.8), g: 0.2 }
+    }
+
+    pub fn with_albedo(mut self, albedo: Vector3) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    pub fn with_phase(mut self, g: f32) -> Self {
+        self.g = g.clamp(-0.95, 0.95);
+        self
+    }
+
+    /// tramo del rayo dentro de la caja, recortado a [0, tmax]
+    fn span(&self, ro: &Vector3, rd: &Vector3, tmax: f32) -> Option<(f32, f32)> {
+        let inv = Vector3::new(1.0 / rd.x, 1.0 / rd.y, 1.0 / rd.z);
+        let axis = |lo: f32, hi: f32, o: f32, i: f32| { let (a, b) = ((lo - o) * i, (hi - o) * i); (a.min(b), a.max(b)) };
+        let (ax, bx) = axis(self.min.x, self.max.x, ro.x, inv.x);
+        let (ay, by) = axis(self.min.y, self.max.y, ro.y, inv.y);
+        let (az, bz) = axis(self.min.z, self.max.z, ro.z, inv.z);
+        let t0 = ax.max(ay).max(az).max(0.0);
+        let t1 = bx.min(by).min(bz).min(tmax);
+        (t0 < t1).then_some((t0, t1))
+    }
+
+    /// σt en un punto del mundo
+    pub fn sigma_t(&self, p: Vector3) -> f32 {
+        let s = self.max - self.min;
+        let q = p - self.min;
+        self.grid.sample(Vector3::new(q.x / s.x, q.y / s.y, q.z / s.z)) * self.density
+    }
+
+    /// delta tracking: distancia a la primera colisión real antes de `tmax` (None si el rayo sale);
+    /// `rd` normalizado
+    pub fn sample_collision(&self, ro: &Vector3, rd: &Vector3, tmax: f32, rng: &mut Rng) -> Option<f32> {
+        let (mut t, t1) = self.span(ro, rd, tmax)?;
+        let sigma_max = self.grid.max * self.density;
+        if sigma_max <= 0.0 { return None; }
+        loop {
+            // paso libre en el medio mayorante; la colisión es real con probabilidad σt/σmax
+            t -= (1.0 - rng.next_f32()).ln() / sigma_max;
+            if t >= t1 { return None; }
+            if self.sigma_t(*ro + *rd * t) > rng.next_f32() * sigma_max { return Some(t); }
+        }
+    }
+
+    /// transmitancia hasta `tmax` a pasos fijos (determinista: rayos de sombra)
+    pub fn transmittance(&self, ro: &Vector3, rd: &Vector3, tmax: f32) -> f32 {
+        const STEPS: u32 = 24;
+        let Some((t0, t1)) = self.span(ro, rd, tmax) else { return 1.0; };
+        let ds = (t1 - t0) / STEPS as f32;
+        let tau: f32 = (0..STEPS).map(|i| self.sigma_t(*ro + *rd * (t0 + (i as f32 + 0.5) * ds))).sum();
+        (-tau * ds).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tmp(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("volume_test_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn loads_text_grid() {
+        let path = write_tmp("ok.txt", b"2 1 1 # dims\n0.25 1\n");
+        let g = DensityGrid::load_text(&path).unwrap();
+        assert_eq!((g.nx, g.ny, g.nz, g.max), (2, 1, 1, 1.0));
+    }
+
+    #[test]
+    fn rejects_empty_and_unparsable_grids() {
+        for (name, text) in [("zero.txt", &b"0 0 0\n"[..]), ("nan.txt", b"2 1 1\n0.5 humo\n"), ("short.txt", b"2 2 1\n1\n")] {
+            let err = DensityGrid::load_text(&write_tmp(name, text)).err().expect(name);
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", name);
+        }
+        let err = DensityGrid::load_raw(&write_tmp("zero.raw", b""), 0, 4, 4).err().expect("zero.raw");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
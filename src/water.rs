@@ -0,0 +1,103 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+/// una ola de Gerstner en el plano XZ
+#[derive(Clone, Copy, Debug)]
+pub struct Wave {
+    pub dir: (f32, f32),  // dirección de avance (normalizada)
+    pub wavelength: f32,  // unidades de mundo
+    pub amplitude: f32,
+    pub steepness: f32,   // 0 = senoidal, 1 = crestas agudas
+}
+
+impl Wave {
+    /// ola hacia el ángulo `angle` (rad, desde +X hacia +Z)
+    pub fn new(angle: f32, wavelength: f32, amplitude: f32, steepness: f32) -> Self {
+        Self { dir: (angle.cos(), angle.sin()), wavelength, amplitude, steepness }
+    }
+}
+
+/// superficie de agua: la primitiva de adentro da la forma, las olas perturban la normal
+/// de las caras que miran hacia arriba según el tiempo (sin desplazar la geometría)
+pub struct Water<T: RayIntersect> {
+    pub inner: T,
+    pub waves: Vec<Wave>,
+    pub drift: (f32, f32), // deslizamiento de la textura (uv por segundo)
+}
+
+impl<T: RayIntersect> Water<T> {
+    pub fn new(inner: T, waves: Vec<Wave>) -> Self {
+        Self { inner, waves, drift: (0.0, 0.0) }
+    }
+
+    pub fn with_drift(mut self, du: f32, dv: f32) -> Self {
+        self.drift = (du, dv);
+        self
+    }
+
+    /// normal de la suma de olas de Gerstner en (x, z) al instante `time` (aguas profundas: ω² = g·k)
+    pub fn normal_at(&self, x: f32, z: f32, time: f32) -> Vector3 {
+        let mut n = Vector3::new(0.0, 1.0, 0.0);
+        for w in &self.waves {
+            let k = 2.0 * PI / w.wavelength.max(1e-3);
+            let omega = (9.8 * k).sqrt();
+            let phase = k * (w.dir.0 * x + w.dir.1 * z) - omega * time;
+            let wa = k * w.amplitude;
+            let (s, c) = phase.sin_cos();
+            n.x -= w.dir.0 * wa * c;
+            n.z -= w.dir.1 * wa * c;
+            n.y -= w.steepness * wa * s;
+        }
+        n.normalized()
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Water<T> {
+    fn ray_intersect(&self, ro: &Vector3, rd: &Vector3) -> Intersect {
+        self.ray_intersect_at(ro, rd, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ro: &Vector3, rd: &Vector3, time: f32) -> Intersect {
+        let mut hit = self.inner.ray_intersect_at(ro, rd, time);
+        if !hit.hit || hit.normal.y < 0.5 { return hit; }
+        // gira la base tangente con la normal para que los mapas de normales sigan valiendo
+        let n = self.normal_at(hit.point.x, hit.point.z, time);
+        hit.normal = n;
+        hit.tangent = (hit.tangent - n * hit.tangent.dot(n)).normalized();
+        hit.bitangent = n.cross(hit.tangent) * if hit.bitangent.dot(n.cross(hit.tangent)) < 0.0 { -1.0 } else { 1.0 };
+        hit.uv = (hit.uv.0 + self.drift.0 * time, hit.uv.1 + self.drift.1 * time);
+        hit
+    }
+
+    fn is_animated(&self) -> bool { !self.waves.is_empty() || self.inner.is_animated() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+
+    fn pond(waves: Vec<Wave>) -> Water<Cube> {
+        Water::new(Cube::from_center_size(Vector3::zero(), Vector3::one(), Material::black()), waves)
+    }
+
+    #[test]
+    fn gerstner_normals_are_unit_and_point_up() {
+        // las olas del estanque de la escena
+        let water = pond(vec![
+            Wave::new(0.3, 0.9, 0.012, 0.5),
+            Wave::new(1.9, 0.55, 0.007, 0.5),
+            Wave::new(-0.8, 0.35, 0.004, 0.4),
+            Wave::new(2.6, 0.2, 0.002, 0.3),
+        ]);
+        for i in 0..200 {
+            let (x, z, t) = (i as f32 * 0.137 - 10.0, i as f32 * 0.291 - 25.0, i as f32 * 0.05);
+            let n = water.normal_at(x, z, t);
+            assert!((n.length() - 1.0).abs() < 1e-5 && n.y > 0.0, "{:?}", n);
+        }
+        let n = pond(Vec::new()).normal_at(0.3, -1.2, 4.0);
+        assert_eq!((n.x, n.y, n.z), (0.0, 1.0, 0.0));
+    }
+}